@@ -0,0 +1,39 @@
+// the pages load htmx from the embedded `dist/`, so without it nothing works.
+// `npm ci` copies it there.
+
+/// the subresource integrity hash the CDN `script` tag pinned htmx 2.0.6 to
+const HTMX_INTEGRITY: &str =
+    "sha384-Akqfrbj/HpNVo8k11SXBb6TlBWmXXlYQrCSqEWmyKJe+hDm3Z/B2WVG4smwBkRVm";
+
+fn main() {
+    use base64::Engine;
+    use sha2::Digest;
+
+    println!("cargo::rerun-if-changed=dist/htmx.min.js");
+
+    match std::fs::read("dist/htmx.min.js") {
+        Ok(htmx) => {
+            let integrity = format!(
+                "sha384-{}",
+                base64::engine::general_purpose::STANDARD.encode(sha2::Sha384::digest(&htmx))
+            );
+
+            if integrity != HTMX_INTEGRITY {
+                panic!(
+                    "dist/htmx.min.js has integrity {integrity}, expected {HTMX_INTEGRITY}. run `npm ci` to reinstall it"
+                );
+            }
+        }
+        Err(_) => {
+            let message = "dist/htmx.min.js is missing, run `npm ci` to install it";
+
+            // release builds embed `dist/`, so the binary would never have htmx.
+            // debug builds read `dist/` from disk at runtime, so installing it later is enough.
+            if std::env::var("PROFILE").as_deref() == Ok("release") {
+                panic!("{message}");
+            }
+
+            println!("cargo::warning={message}");
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// vim-style keyboard shortcuts.
+// everything here goes through the same links and htmx endpoints
+// as clicking on things would.
+(() => {
+  // the entry rows on feed_show, including any that "Load more" added
+  const rows = () => Array.from(document.querySelectorAll("tr[data-entry-id]"));
+
+  let selected = -1;
+
+  const select = (i) => {
+    const all = rows();
+
+    if (all.length === 0) {
+      return;
+    }
+
+    selected = Math.max(0, Math.min(i, all.length - 1));
+
+    all.forEach((row, j) => row.classList.toggle("bg-base-200", j === selected));
+    all[selected].scrollIntoView({ block: "nearest" });
+  };
+
+  const selectedRow = () => rows()[selected];
+
+  const go = (selector) => {
+    const link = document.querySelector(selector);
+
+    if (link) {
+      window.location.href = link.href;
+    }
+  };
+
+  const toggleRead = () => {
+    const row = selectedRow();
+
+    if (row) {
+      htmx
+        .ajax("PUT", `/entries/${row.dataset.entryId}?action=toggle_read_unread`, { swap: "none" })
+        .then(() => row.classList.toggle("opacity-50"));
+    } else if (document.getElementById("read-toggle")) {
+      htmx.trigger("#read-toggle", "click");
+    }
+  };
+
+  const openOriginal = () => {
+    const row = selectedRow();
+    const link = row
+      ? row.querySelector("a[target=_blank]")
+      : document.getElementById("original-link");
+
+    if (link) {
+      window.open(link.href, "_blank");
+    }
+  };
+
+  // the previous key, for two key shortcuts like "g i"
+  let pending = null;
+
+  document.addEventListener("keydown", (event) => {
+    if (event.ctrlKey || event.metaKey || event.altKey) {
+      return;
+    }
+
+    if (event.target.closest("input, textarea, select, [contenteditable]")) {
+      return;
+    }
+
+    const previous = pending;
+    pending = null;
+
+    if (previous === "g") {
+      if (event.key === "i") {
+        window.location.href = "/";
+      }
+      return;
+    }
+
+    switch (event.key) {
+      case "j":
+        if (rows().length > 0) {
+          select(selected + 1);
+        } else {
+          go("#next-entry");
+        }
+        break;
+      case "k":
+        if (rows().length > 0) {
+          select(selected - 1);
+        } else {
+          go("#previous-entry");
+        }
+        break;
+      case "o":
+      case "Enter":
+        if (selectedRow()) {
+          event.preventDefault();
+          window.location.href = selectedRow().querySelector("a[href^='/entries/']").href;
+        }
+        break;
+      case "m":
+        toggleRead();
+        break;
+      case "v":
+        openOriginal();
+        break;
+      case "r":
+        if (document.getElementById("refresher")) {
+          htmx.trigger("#refresher", "click");
+        }
+        break;
+      case "g":
+        pending = "g";
+        break;
+      case "?":
+        document.getElementById("shortcuts-help").showModal();
+        break;
+    }
+  });
+})();
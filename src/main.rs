@@ -6,24 +6,24 @@
 // - [ ] nav on mobile (???)
 // - [ ] figure out why <hr> won't show up at bottom of entry
 // - [x] figure out a name for this, is russweb good?
-// - [ ] search???
-// - [ ] on_index: refresh all feeds
-// - [ ] on index: sort by arbitrary columns
+// - [x] search???
+// - [x] on_index: refresh all feeds
+// - [x] on index: sort by arbitrary columns
 // - [x] on feed_show: show read entries
 // - [x] on feed_show: show unread entries
 // - [x] on feed_show: show all entries
 // - [x] on feed_show: navigate back to index
 // - [x] on feed_show: refresh feed
 // - [ ] on feed_show: errors for refreshing a feed
-// - [ ] on feed_show: sort on arbitrary columns
-// - [ ] on feed_show: delete entry
+// - [x] on feed_show: sort on arbitrary columns
+// - [x] on feed_show: delete entry
 // - [x] on entry_show: navigate back to feed
 // - [x] on entry_show: navigate to other entry in feed
 // - [ ] on entry_show: the pub_date should look better
 // - [x] on entry_show: mark read
 // - [x] on entry_show: mark unread
 // - [x] on entry_show: make sure entry text wraps on mobile
-// - [ ] on entry_show: delete entry
+// - [x] on entry_show: delete entry
 // - [ ] pick a default database location
 // - [x] rust-embed for css
 // - [x] set up CI
@@ -34,17 +34,21 @@ use axum::extract::{Path, Query, State};
 use axum::http::{HeaderMap, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{delete, get, post, put};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use futures_util::TryStreamExt;
 use maud::{PreEscaped, html};
 use rust_embed::Embed;
 use serde::Deserialize;
 use sqlx::prelude::FromRow;
 use sqlx::{Connection, Executor, Sqlite};
 use std::collections::HashSet;
+use std::io::IsTerminal;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
 use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tracing::instrument;
 
 macro_rules! layout {
@@ -65,19 +69,44 @@ macro_rules! layout {
                     }
                     "
                 }
-                script
-                    src="https://cdn.jsdelivr.net/npm/htmx.org@2.0.6/dist/htmx.min.js"
-                    integrity="sha384-Akqfrbj/HpNVo8k11SXBb6TlBWmXXlYQrCSqEWmyKJe+hDm3Z/B2WVG4smwBkRVm"
-                    crossorigin="anonymous" {}
-                link href="/dist/output.css" rel="stylesheet";
+                script src=(asset_href("htmx.min.js")) {}
+                link href=(asset_href("output.css")) rel="stylesheet";
+                script src=(asset_href("shortcuts.js")) defer {}
             }
             body {
                 div class="grid container mx-auto px-4" {
                     ($content)
                 }
+                dialog id="shortcuts-help" class="modal" {
+                    div class="modal-box" {
+                        h3 class="text-lg font-bold" {
+                            "Keyboard shortcuts"
+                        }
+                        table class="table table-sm" {
+                            tbody {
+                                tr { td { kbd class="kbd" { "j" } " / " kbd class="kbd" { "k" } } td { "Next / previous entry" } }
+                                tr { td { kbd class="kbd" { "o" } " / " kbd class="kbd" { "enter" } } td { "Open the selected entry" } }
+                                tr { td { kbd class="kbd" { "m" } } td { "Toggle read" } }
+                                tr { td { kbd class="kbd" { "v" } } td { "View original" } }
+                                tr { td { kbd class="kbd" { "r" } } td { "Refresh" } }
+                                tr { td { kbd class="kbd" { "g" } " " kbd class="kbd" { "i" } } td { "Go to the index" } }
+                                tr { td { kbd class="kbd" { "?" } } td { "Show this help" } }
+                            }
+                        }
+                    }
+                    form method="dialog" class="modal-backdrop" {
+                        button { "close" }
+                    }
+                }
                 script {
                     "document.body.addEventListener('feedCreateError', function(evt){
                         alert(evt.detail.value);
+                    })
+                    document.body.addEventListener('folderCreateError', function(evt){
+                        alert(evt.detail.value);
+                    })
+                    document.body.addEventListener('feedDeleteError', function(evt){
+                        alert(evt.detail.value);
                     })"
                 }
             }
@@ -87,7 +116,8 @@ macro_rules! layout {
 
 #[instrument(skip(state))]
 async fn feed_index(
-    State(state): State<Arc<Mutex<AppState>>>,
+    State(state): State<AppState>,
+    Query(sort): Query<Sort<FeedsSortColumn>>,
 ) -> Result<impl IntoResponse, AppError> {
     #[derive(FromRow)]
     struct Feed {
@@ -97,31 +127,78 @@ async fn feed_index(
         read_entries: i64,
         most_recent_entry: String,
         refreshed_at: String,
+        folder_id: Option<i64>,
     }
 
-    let state = state.lock().await;
+    #[derive(FromRow)]
+    struct Folder {
+        id: i64,
+        title: String,
+    }
 
     let mut conn = state.pool.acquire().await?;
 
-    let feeds: Vec<Feed> = sqlx::query_as(
-        "
+    let feeds: Vec<Feed> = {
+        let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+            "
     select
         feeds.id,
         feeds.title,
-        sum(case when entries.read_at then 0 else 1 end) as unread_entries,
-        sum(case when entries.read_at then 1 else 0 end) as read_entries,
+        count(entries.id) - count(entries.read_at) as unread_entries,
+        count(entries.read_at) as read_entries,
         max(coalesce(entries.pub_date, entries.inserted_at)) as most_recent_entry,
-        feeds.refreshed_at
+        feeds.refreshed_at,
+        feeds.folder_id
     from feeds
-    inner join entries
+    left join entries
         on entries.feed_id = feeds.id
     group by feeds.id
-    order by feeds.title asc
+    ",
+        );
+
+        qb.push(format!(
+            " order by {} {}, feeds.title asc ",
+            sort.column().expression(),
+            sort.direction().as_sql()
+        ));
+
+        qb.build_query_as().fetch_all(&mut *conn).await?
+    };
+
+    let folders: Vec<Folder> = sqlx::query_as(
+        "
+    select
+        id,
+        title
+    from folders
+    order by title asc
     ",
     )
     .fetch_all(&mut *conn)
     .await?;
 
+    // folders in title order, then the feeds that aren't in a folder
+    let mut sections: Vec<(Option<&Folder>, Vec<&Feed>)> = folders
+        .iter()
+        .map(|folder| {
+            (
+                Some(folder),
+                feeds
+                    .iter()
+                    .filter(|feed| feed.folder_id == Some(folder.id))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    sections.push((
+        None,
+        feeds
+            .iter()
+            .filter(|feed| feed.folder_id.is_none())
+            .collect(),
+    ));
+
     Ok(layout! {
         html! {
             div class="p-4" {
@@ -133,28 +210,96 @@ async fn feed_index(
                 {
                     "Add feed"
                 }
-                table class="table" {
-                    thead {
-                        tr {
-                            th { "Feed" }
-                            th class="hidden sm:table-cell" { "Most recent entry" }
-                            th class="hidden sm:table-cell" { "Last refreshed" }
-                            th class="hidden sm:table-cell" { "Unread entries" }
-                            th class="hidden sm:table-cell" { "Read entries" }
-                        }
-                    }
-                    tbody {
-                        @for feed in feeds {
-                            tr {
-                                td class="text-center sm:text-left" {
-                                    a class="link" href=(format!("/feeds/{}", feed.id)) {
-                                        (feed.title)
+                a
+                    class="link p-2"
+                    hx-post="/folders"
+                    hx-prompt="Folder name"
+                    hx-swap="none"
+                {
+                    "Add folder"
+                }
+                a
+                    id="refresher"
+                    class="link p-2"
+                    hx-put="/feeds/refresh"
+                    hx-swap="afterend"
+                {
+                    "Refresh all feeds"
+                }
+                a class="link p-2" href="/feeds/import" {
+                    "Import OPML"
+                }
+                a class="link p-2" href="/feeds/export" {
+                    "Export OPML"
+                }
+                a class="link p-2" href="/search" {
+                    "Search"
+                }
+                a class="link p-2" href="/starred" {
+                    "Starred"
+                }
+                a class="link p-2" href="/entries" {
+                    "River"
+                }
+                a class="link p-2" hx-post="/logout" {
+                    "Log out"
+                }
+                (mark_read_form("/feeds/mark_read"))
+                div
+                    id="feeds-table"
+                    hx-get=(format!("/?{}", sort.query_string()))
+                    hx-trigger="entriesRead from:body"
+                    hx-select="#feeds-table"
+                    hx-swap="outerHTML"
+                {
+                    @for (folder, feeds) in sections {
+                        // don't show an empty "Unfiled" when everything is in a folder
+                        @if folder.is_some() || !feeds.is_empty() {
+                            details class="py-2" open {
+                                summary class="cursor-pointer" {
+                                    @if let Some(folder) = folder {
+                                        a class="link" href=(format!("/folders/{}", folder.id)) {
+                                            (folder.title)
+                                        }
+                                    } @else {
+                                        "Unfiled"
+                                    }
+                                    (format!(" ({} unread)", feeds.iter().map(|feed| feed.unread_entries).sum::<i64>()))
+                                }
+                                table class="table" {
+                                    thead {
+                                        tr {
+                                            th { (sort_header("Feed", "/?", sort, FeedsSortColumn::Title)) }
+                                            th class="hidden sm:table-cell" {
+                                                (sort_header("Most recent entry", "/?", sort, FeedsSortColumn::MostRecentEntry))
+                                            }
+                                            th class="hidden sm:table-cell" {
+                                                (sort_header("Last refreshed", "/?", sort, FeedsSortColumn::RefreshedAt))
+                                            }
+                                            th class="hidden sm:table-cell" {
+                                                (sort_header("Unread entries", "/?", sort, FeedsSortColumn::UnreadEntries))
+                                            }
+                                            th class="hidden sm:table-cell" {
+                                                (sort_header("Read entries", "/?", sort, FeedsSortColumn::ReadEntries))
+                                            }
+                                        }
+                                    }
+                                    tbody {
+                                        @for feed in feeds {
+                                            tr {
+                                                td class="text-center sm:text-left" {
+                                                    a class="link" href=(format!("/feeds/{}", feed.id)) {
+                                                        (feed.title)
+                                                    }
+                                                }
+                                                td class="hidden sm:table-cell" { (feed.most_recent_entry) }
+                                                td class="hidden sm:table-cell" { (feed.refreshed_at) }
+                                                td class="hidden sm:table-cell" { (feed.unread_entries) }
+                                                td class="hidden sm:table-cell" { (feed.read_entries) }
+                                            }
+                                        }
                                     }
                                 }
-                                td class="hidden sm:table-cell" { (feed.most_recent_entry) }
-                                td class="hidden sm:table-cell" { (feed.refreshed_at) }
-                                td class="hidden sm:table-cell" { (feed.unread_entries) }
-                                td class="hidden sm:table-cell" { (feed.read_entries) }
                             }
                         }
                     }
@@ -164,40 +309,95 @@ async fn feed_index(
     })
 }
 
-#[derive(Deserialize, Debug)]
-struct FeedShowParams {
-    entries_visibility: Option<EntriesVisibility>,
+#[derive(Debug, Error)]
+enum FolderCreateError {
+    #[error("bad input")]
+    BadInput(&'static str),
+    #[error("folder already exists")]
+    AlreadyExists,
+    #[error("database error")]
+    DatabaseError(#[from] sqlx::Error),
 }
 
-#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
-enum EntriesVisibility {
-    #[default]
-    #[serde(rename = "unread")]
-    Unread,
-    #[serde(rename = "read")]
-    Read,
-    #[serde(rename = "all")]
-    All,
-}
+async fn do_folder_create(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<(), FolderCreateError> {
+    let title = headers
+        .get("HX-Prompt")
+        .ok_or(FolderCreateError::BadInput(
+            "somehow the HX-Prompt header did not get included",
+        ))?
+        .to_str()
+        .map_err(|_| FolderCreateError::BadInput("could not convert HX-Prompt value to str"))?
+        .trim();
 
-impl EntriesVisibility {
-    fn is_read(&self) -> bool {
-        *self == Self::Read
+    if title.is_empty() {
+        return Err(FolderCreateError::BadInput("Folder name can't be empty"));
     }
 
-    fn is_all(&self) -> bool {
-        *self == Self::All
+    let mut conn = state.pool.acquire().await?;
+
+    sqlx::query(
+        "
+        insert into folders (title)
+        values (?)",
+    )
+    .bind(title)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(e) if e.is_unique_violation() => FolderCreateError::AlreadyExists,
+        e => e.into(),
+    })?;
+
+    Ok(())
+}
+
+async fn folder_create(
+    headers: HeaderMap,
+    state: State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    match do_folder_create(headers, state).await {
+        Ok(()) => {
+            let mut headers = HeaderMap::new();
+            headers.insert("HX-Location", "/".parse().unwrap());
+            Ok((headers, "").into_response())
+        }
+        Err(e) => {
+            let (status_code, error_message) = match e {
+                FolderCreateError::BadInput(s) => (StatusCode::BAD_REQUEST, s.to_string()),
+                FolderCreateError::AlreadyExists => {
+                    (StatusCode::BAD_REQUEST, "Folder already exists".to_string())
+                }
+                FolderCreateError::DatabaseError(e) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Database error: {e}"),
+                ),
+            };
+
+            let mut headers = HeaderMap::new();
+
+            headers.insert(
+                "HX-Trigger",
+                axum::Json(format!("{{\"folderCreateError\":\"{error_message}\"}}"))
+                    .parse()
+                    .unwrap(),
+            );
+
+            Ok((status_code, headers, "").into_response())
+        }
     }
 }
 
 #[instrument(skip(state))]
-async fn feed_show(
-    State(state): State<Arc<Mutex<AppState>>>,
-    Path(feed_id): Path<i64>,
+async fn folder_show(
+    State(state): State<AppState>,
+    Path(folder_id): Path<i64>,
     Query(params): Query<FeedShowParams>,
 ) -> Result<impl IntoResponse, AppError> {
     #[derive(FromRow)]
-    struct Feed {
+    struct Folder {
         title: String,
     }
 
@@ -205,23 +405,24 @@ async fn feed_show(
     struct Entry {
         id: i64,
         title: String,
+        feed_id: i64,
+        feed_title: String,
         pub_date: String,
-        link: String,
         read_at: Option<String>,
     }
 
-    let state = state.lock().await;
+    let entries_visibility = params.entries_visibility.unwrap_or_default();
 
     let mut conn = state.pool.acquire().await?;
 
-    let feed: Feed = sqlx::query_as(
+    let folder: Folder = sqlx::query_as(
         "
         select
             title
-        from feeds
+        from folders
         where id = ?",
     )
-    .bind(feed_id)
+    .bind(folder_id)
     .fetch_one(&mut *conn)
     .await?;
 
@@ -229,32 +430,23 @@ async fn feed_show(
         let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
             "
         select
-            id,
-            title,
-            pub_date,
-            link,
-            read_at
+            entries.id,
+            entries.title,
+            entries.feed_id,
+            feeds.title as feed_title,
+            entries.pub_date,
+            entries.read_at
         from entries
-        where feed_id = ",
+        inner join feeds
+            on feeds.id = entries.feed_id
+        where feeds.folder_id = ",
         );
 
-        qb.push_bind(feed_id);
+        qb.push_bind(folder_id);
 
-        if let Some(entries_visibility) = params.entries_visibility {
-            match entries_visibility {
-                EntriesVisibility::Unread => {
-                    qb.push(" and read_at is null ");
-                }
-                EntriesVisibility::Read => {
-                    qb.push(" and read_at is not null ");
-                }
-                EntriesVisibility::All => {}
-            }
-        } else {
-            qb.push(" and read_at is null ");
-        }
+        qb.push(entries_visibility.read_at_condition());
 
-        qb.push(" order by pub_date desc ");
+        qb.push(" order by entries.pub_date desc ");
 
         qb.build_query_as().fetch_all(&mut *conn).await?
     };
@@ -269,8 +461,8 @@ async fn feed_show(
                         }
                     }
                     li {
-                        a href=(format!("/feeds/{}", feed_id)) {
-                            (feed.title)
+                        a href=(format!("/folders/{folder_id}")) {
+                            (folder.title)
                         }
                     }
                 }
@@ -278,62 +470,33 @@ async fn feed_show(
             div class="p-2" {
                 header class="flex flex-wrap justify-start" {
                     h1 {
-                        (feed.title)
+                        (folder.title)
                     }
                     div {
                         a
-                            id="refresher"
                             class="link p-2"
-                            hx-put=(format!("/feeds/{feed_id}/refresh"))
-                            // the parent div is the target
-                            hx-target="closest :not(#refresher)"
-                            // so we append "afterend" the parent div
-                            hx-swap="afterend"
+                            hx-delete=(format!("/folders/{folder_id}"))
+                            hx-confirm=(format!("Delete the folder {}? Its feeds will be kept.", folder.title))
+                            hx-swap="none"
                         {
-                            "Refresh feed"
+                            "Delete folder"
                         }
                     }
-                    // class=ml-auto here is a hack to get things to go to the right
-                    // there is probably a better way to do this,
-                    // will probably reevaluate this nav functionality entirely
                     div class="ml-auto" {
-                        @match params.entries_visibility {
-                            Some(v) => {
-                                @match v {
-                                    EntriesVisibility::Unread => {
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read")) {
-                                            "View read entries"
-                                        }
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all")) {
-                                            "View all entries"
-                                        }
-                                    },
-                                    EntriesVisibility::Read => {
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=unread")) {
-                                            "View unread entries"
-                                        }
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all")) {
-                                            "View all entries"
-                                        }
-                                    },
-                                    EntriesVisibility::All => {
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=unread")) {
-                                            "View unread entries"
-                                        }
-                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read")) {
-                                            "View read entries"
-                                        }
-                                    },
-                                }
-                            },
-                            None => {
-                                a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read")) {
-                                    "View read entries"
-                                }
-                                a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all")) {
-                                    "View all entries"
-                                }
-                            },
+                        @if entries_visibility != EntriesVisibility::Unread {
+                            a class="link p-2" href=(format!("/folders/{folder_id}?entries_visibility=unread")) {
+                                "View unread entries"
+                            }
+                        }
+                        @if !entries_visibility.is_read() {
+                            a class="link p-2" href=(format!("/folders/{folder_id}?entries_visibility=read")) {
+                                "View read entries"
+                            }
+                        }
+                        @if !entries_visibility.is_all() {
+                            a class="link p-2" href=(format!("/folders/{folder_id}?entries_visibility=all")) {
+                                "View all entries"
+                            }
                         }
                     }
                 }
@@ -342,11 +505,11 @@ async fn feed_show(
                         thead {
                             tr {
                                 th { "Title" }
+                                th class="hidden sm:table-cell" { "Feed" }
                                 th class="hidden sm:table-cell" { "Publication date" }
-                                @if params.entries_visibility.map(|v| v.is_read() || v.is_all()).unwrap_or(false) {
+                                @if entries_visibility.is_read() || entries_visibility.is_all() {
                                     th class="hidden sm:table-cell" { "Read at" }
                                 }
-                                th class="hidden sm:table-cell" { "" }
                             }
                         }
                         tbody {
@@ -357,19 +520,15 @@ async fn feed_show(
                                             (entry.title)
                                         }
                                     }
-                                    td class="hidden sm:table-cell" { (entry.pub_date) }
-                                    @if params.entries_visibility.map(|v| v.is_read() || v.is_all()).unwrap_or(false) {
-                                        td class="hidden sm:table-cell" { (entry.read_at.unwrap_or_else(String::new)) }
-                                    }
                                     td class="hidden sm:table-cell" {
-                                        a
-                                            class="link"
-                                            href=(entry.link)
-                                            target="_blank"
-                                        {
-                                            "View original"
+                                        a class="link" href=(format!("/feeds/{}", entry.feed_id)) {
+                                            (entry.feed_title)
                                         }
                                     }
+                                    td class="hidden sm:table-cell" { (entry.pub_date) }
+                                    @if entries_visibility.is_read() || entries_visibility.is_all() {
+                                        td class="hidden sm:table-cell" { (entry.read_at.unwrap_or_else(String::new)) }
+                                    }
                                 }
                             }
                         }
@@ -381,71 +540,409 @@ async fn feed_show(
 }
 
 #[instrument(skip(state))]
-async fn entry_show(
-    State(state): State<Arc<Mutex<AppState>>>,
-    Path(entry_id): Path<i64>,
+async fn folder_delete(
+    State(state): State<AppState>,
+    Path(folder_id): Path<i64>,
 ) -> Result<impl IntoResponse, AppError> {
-    #[derive(FromRow)]
-    struct Entry {
-        feed_id: i64,
-        title: String,
-        // author: String,
-        description: String,
-        content: String,
-        pub_date: String,
-        link: String,
-        read_at: Option<String>,
-    }
+    // its feeds go back to being unfiled via `on delete set null`
+    sqlx::query(
+        "
+        delete from folders
+        where id = ?",
+    )
+    .bind(folder_id)
+    .execute(&state.pool)
+    .await?;
 
-    #[derive(FromRow)]
-    struct Feed {
-        title: String,
-    }
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Location", "/".parse().unwrap());
+    Ok((headers, ""))
+}
 
-    let state = state.lock().await;
+#[derive(Deserialize, Debug)]
+struct FeedFolderParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    folder_id: Option<i64>,
+}
 
+#[instrument(skip(state))]
+async fn feed_folder_update(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+    axum::Form(params): axum::Form<FeedFolderParams>,
+) -> Result<impl IntoResponse, AppError> {
     let mut conn = state.pool.acquire().await?;
 
-    let entry: Entry = sqlx::query_as(
+    sqlx::query(
         "
-        select
-            feed_id,
-            title,
-            author,
-            description,
-            content,
-            pub_date,
-            link,
-            read_at
-        from entries
-        where id = ?
-        ",
+        update feeds
+        set
+            folder_id = ?1,
+            updated_at = ?2
+        where id = ?3",
     )
-    .bind(entry_id)
-    .fetch_one(&mut *conn)
+    .bind(params.folder_id)
+    .bind(chrono::Utc::now())
+    .bind(feed_id)
+    .execute(&mut *conn)
     .await?;
 
-    let feed: Feed = sqlx::query_as(
-        "
-        select
-            title
-        from feeds
-        where id = ?
-        ",
-    )
-    .bind(entry.feed_id)
-    .fetch_one(&mut *conn)
-    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let content = if entry.content.len() >= entry.description.len() {
-        entry.content
-    } else {
-        entry.description
-    };
+#[derive(Deserialize, Debug)]
+struct FeedShowParams {
+    entries_visibility: Option<EntriesVisibility>,
+    #[serde(flatten)]
+    cursor: EntriesCursor,
+    #[serde(flatten)]
+    sort: Sort<EntriesSortColumn>,
+}
 
-    let cleaned = ammonia::clean(&content);
+/// how many entries a paginated entry list shows at a time
+const ENTRIES_PAGE_SIZE: i64 = 50;
 
-    Ok(layout! {
+/// where the previous page of an entry list left off:
+/// the value of the sort column and the id of the last entry shown
+#[derive(Deserialize, Debug, Default)]
+struct EntriesCursor {
+    // an empty string is a real value here, e.g. an entry with an empty title
+    #[serde(default)]
+    after: Option<String>,
+    /// set instead of `after` when the sort column was null
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    after_null: Option<bool>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    after_id: Option<i64>,
+}
+
+impl EntriesCursor {
+    /// the cursor for the page after the entry with this sort value and `id`
+    fn after(value: Option<&str>, id: i64) -> Self {
+        Self {
+            after: value.map(|value| value.to_owned()),
+            after_null: value.is_none().then_some(true),
+            after_id: Some(id),
+        }
+    }
+
+    /// the cursor for the page after the entry with this `pub_date` and `id`.
+    /// sqlx decodes a null `pub_date` as an empty string, so that means null here
+    fn after_pub_date(pub_date: &str, id: i64) -> Self {
+        Self::after(Some(pub_date).filter(|pub_date| !pub_date.is_empty()), id)
+    }
+
+    /// the sort value the cursor is after, `None` when it was null
+    fn value(&self) -> Option<&String> {
+        if self.after_null == Some(true) {
+            None
+        } else {
+            self.after.as_ref()
+        }
+    }
+
+    /// restricts the query to the entries after the cursor in `sort` order,
+    /// fetching one extra entry to tell whether there is another page.
+    /// sqlite sorts nulls first, so they come last when sorting descending
+    fn push_page(&self, qb: &mut sqlx::QueryBuilder<'_, Sqlite>, sort: Sort<EntriesSortColumn>) {
+        let column = sort.column().expression();
+
+        if let Some(id) = self.after_id {
+            match (sort.direction(), self.value()) {
+                (SortDirection::Desc, Some(value)) => {
+                    qb.push(format!(" and ({column} < "));
+                    qb.push_bind(value.clone());
+                    qb.push(format!(" or ({column} = "));
+                    qb.push_bind(value.clone());
+                    qb.push(" and entries.id < ");
+                    qb.push_bind(id);
+                    qb.push(format!(") or {column} is null) "));
+                }
+                (SortDirection::Desc, None) => {
+                    qb.push(format!(" and {column} is null and entries.id < "));
+                    qb.push_bind(id);
+                }
+                (SortDirection::Asc, Some(value)) => {
+                    qb.push(format!(" and ({column} > "));
+                    qb.push_bind(value.clone());
+                    qb.push(format!(" or ({column} = "));
+                    qb.push_bind(value.clone());
+                    qb.push(" and entries.id > ");
+                    qb.push_bind(id);
+                    qb.push(")) ");
+                }
+                (SortDirection::Asc, None) => {
+                    qb.push(format!(" and (({column} is null and entries.id > "));
+                    qb.push_bind(id);
+                    qb.push(format!(") or {column} is not null) "));
+                }
+            }
+        }
+
+        let direction = sort.direction().as_sql();
+
+        qb.push(format!(
+            " order by {column} {direction}, entries.id {direction} limit "
+        ));
+        qb.push_bind(ENTRIES_PAGE_SIZE + 1);
+    }
+
+    /// the query parameters for the page starting at this cursor
+    fn query_string(&self) -> String {
+        let mut pairs = vec![];
+
+        match self.value() {
+            Some(value) => pairs.push(("after", value.clone())),
+            None if self.after_id.is_some() => pairs.push(("after_null", "true".to_string())),
+            None => {}
+        }
+
+        if let Some(id) = self.after_id {
+            pairs.push(("after_id", id.to_string()));
+        }
+
+        serde_html_form::to_string(pairs).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+enum SortDirection {
+    #[serde(rename = "asc")]
+    Asc,
+    #[serde(rename = "desc")]
+    Desc,
+}
+
+impl SortDirection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+
+    fn as_sql(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => " asc ",
+            SortDirection::Desc => " desc ",
+        }
+    }
+
+    fn reversed(&self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+/// a column a table can be sorted on.
+/// only these expressions ever make it into an `order by`
+trait SortColumn: Copy + Default + PartialEq {
+    fn as_str(&self) -> &'static str;
+
+    fn expression(&self) -> &'static str;
+
+    /// the direction you get when you first click on the column's header
+    fn default_direction(&self) -> SortDirection;
+}
+
+/// the `sort` and `dir` query parameters
+#[derive(Clone, Copy, Deserialize, Debug, Default)]
+struct Sort<C> {
+    sort: Option<C>,
+    dir: Option<SortDirection>,
+}
+
+impl<C: SortColumn> Sort<C> {
+    fn column(&self) -> C {
+        self.sort.unwrap_or_default()
+    }
+
+    fn direction(&self) -> SortDirection {
+        self.dir
+            .unwrap_or_else(|| self.column().default_direction())
+    }
+
+    /// the sort you get by clicking on `column`'s header,
+    /// which flips the direction if the table is already sorted by it
+    fn toggled(&self, column: C) -> Self {
+        let dir = if self.column() == column {
+            self.direction().reversed()
+        } else {
+            column.default_direction()
+        };
+
+        Self {
+            sort: Some(column),
+            dir: Some(dir),
+        }
+    }
+
+    fn query_string(&self) -> String {
+        format!(
+            "sort={}&dir={}",
+            self.column().as_str(),
+            self.direction().as_str()
+        )
+    }
+}
+
+/// a table header that sorts the table by `column`.
+/// `href` is the page's path and any other query parameters it needs
+fn sort_header<C: SortColumn>(label: &str, href: &str, sort: Sort<C>, column: C) -> maud::Markup {
+    html! {
+        a class="link" href=(format!("{href}{}", sort.toggled(column).query_string())) {
+            (label)
+            @if sort.column() == column {
+                @match sort.direction() {
+                    SortDirection::Asc => " ▲",
+                    SortDirection::Desc => " ▼",
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+enum FeedsSortColumn {
+    #[default]
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "most_recent_entry")]
+    MostRecentEntry,
+    #[serde(rename = "refreshed_at")]
+    RefreshedAt,
+    #[serde(rename = "unread_entries")]
+    UnreadEntries,
+    #[serde(rename = "read_entries")]
+    ReadEntries,
+}
+
+impl SortColumn for FeedsSortColumn {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FeedsSortColumn::Title => "title",
+            FeedsSortColumn::MostRecentEntry => "most_recent_entry",
+            FeedsSortColumn::RefreshedAt => "refreshed_at",
+            FeedsSortColumn::UnreadEntries => "unread_entries",
+            FeedsSortColumn::ReadEntries => "read_entries",
+        }
+    }
+
+    fn expression(&self) -> &'static str {
+        match self {
+            FeedsSortColumn::Title => "feeds.title",
+            FeedsSortColumn::MostRecentEntry => "most_recent_entry",
+            FeedsSortColumn::RefreshedAt => "feeds.refreshed_at",
+            FeedsSortColumn::UnreadEntries => "unread_entries",
+            FeedsSortColumn::ReadEntries => "read_entries",
+        }
+    }
+
+    fn default_direction(&self) -> SortDirection {
+        match self {
+            FeedsSortColumn::Title => SortDirection::Asc,
+            _ => SortDirection::Desc,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+enum EntriesSortColumn {
+    #[serde(rename = "title")]
+    Title,
+    #[default]
+    #[serde(rename = "pub_date")]
+    PubDate,
+    #[serde(rename = "read_at")]
+    ReadAt,
+}
+
+impl SortColumn for EntriesSortColumn {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntriesSortColumn::Title => "title",
+            EntriesSortColumn::PubDate => "pub_date",
+            EntriesSortColumn::ReadAt => "read_at",
+        }
+    }
+
+    fn expression(&self) -> &'static str {
+        match self {
+            EntriesSortColumn::Title => "entries.title",
+            EntriesSortColumn::PubDate => "entries.pub_date",
+            EntriesSortColumn::ReadAt => "entries.read_at",
+        }
+    }
+
+    fn default_direction(&self) -> SortDirection {
+        match self {
+            EntriesSortColumn::Title => SortDirection::Asc,
+            _ => SortDirection::Desc,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct EntriesIndexParams {
+    entries_visibility: Option<EntriesVisibility>,
+    #[serde(flatten)]
+    cursor: EntriesCursor,
+}
+
+#[instrument(skip(state))]
+async fn entries_index(
+    State(state): State<AppState>,
+    Query(params): Query<EntriesIndexParams>,
+) -> Result<impl IntoResponse, AppError> {
+    #[derive(FromRow)]
+    struct Entry {
+        id: i64,
+        title: String,
+        feed_id: i64,
+        feed_title: String,
+        pub_date: String,
+        read_at: Option<String>,
+    }
+
+    let entries_visibility = params.entries_visibility.unwrap_or_default();
+
+    let mut conn = state.pool.acquire().await?;
+
+    let mut entries: Vec<Entry> = {
+        let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+            "
+        select
+            entries.id,
+            entries.title,
+            entries.feed_id,
+            feeds.title as feed_title,
+            entries.pub_date,
+            entries.read_at
+        from entries
+        inner join feeds
+            on feeds.id = entries.feed_id
+        where true ",
+        );
+
+        qb.push(entries_visibility.read_at_condition());
+
+        params.cursor.push_page(&mut qb, Sort::default());
+
+        qb.build_query_as().fetch_all(&mut *conn).await?
+    };
+
+    let next_page = if entries.len() as i64 > ENTRIES_PAGE_SIZE {
+        entries.pop();
+        entries
+            .last()
+            .map(|entry| EntriesCursor::after_pub_date(&entry.pub_date, entry.id))
+    } else {
+        None
+    };
+
+    Ok(layout! {
         html! {
             div class="breadcrumbs text-sm" {
                 ul {
@@ -455,619 +952,4332 @@ async fn entry_show(
                         }
                     }
                     li {
-                        a href=(format!("/feeds/{}", entry.feed_id)) {
-                            (feed.title)
+                        a href="/entries" {
+                            "Entries"
                         }
                     }
-                    li {
-                        a href=(format!("/entries/{}", entry_id)) {
-                            (entry.title)
+                }
+            }
+            div class="p-2" {
+                header class="flex flex-wrap justify-start" {
+                    div class="ml-auto" {
+                        @if entries_visibility != EntriesVisibility::Unread {
+                            a class="link p-2" href="/entries?entries_visibility=unread" {
+                                "View unread entries"
+                            }
+                        }
+                        @if !entries_visibility.is_read() {
+                            a class="link p-2" href="/entries?entries_visibility=read" {
+                                "View read entries"
+                            }
+                        }
+                        @if !entries_visibility.is_all() {
+                            a class="link p-2" href="/entries?entries_visibility=all" {
+                                "View all entries"
+                            }
+                        }
+                    }
+                }
+                main id="river" {
+                    table class="table" {
+                        thead {
+                            tr {
+                                th { "Title" }
+                                th class="hidden sm:table-cell" { "Feed" }
+                                th class="hidden sm:table-cell" { "Publication date" }
+                                @if entries_visibility.is_read() || entries_visibility.is_all() {
+                                    th class="hidden sm:table-cell" { "Read at" }
+                                }
+                            }
+                        }
+                        tbody {
+                            @for entry in entries {
+                                tr {
+                                    td class="text-center sm:text-left" {
+                                        a class="link" href=(format!("/entries/{}", entry.id)) {
+                                            (entry.title)
+                                        }
+                                    }
+                                    td class="hidden sm:table-cell" {
+                                        a class="link" href=(format!("/feeds/{}", entry.feed_id)) {
+                                            (entry.feed_title)
+                                        }
+                                    }
+                                    td class="hidden sm:table-cell" { (entry.pub_date) }
+                                    @if entries_visibility.is_read() || entries_visibility.is_all() {
+                                        td class="hidden sm:table-cell" { (entry.read_at.unwrap_or_else(String::new)) }
+                                    }
+                                }
+                            }
+                            // replaces itself with the next page's rows when scrolled into view
+                            @if let Some(next_page) = next_page {
+                                tr
+                                    hx-get=(format!(
+                                        "/entries?entries_visibility={}&{}",
+                                        entries_visibility.as_str(),
+                                        next_page.query_string()
+                                    ))
+                                    hx-trigger="revealed"
+                                    hx-select="#river tbody > tr"
+                                    hx-swap="outerHTML"
+                                {
+                                    td colspan="4" {
+                                        "Loading more entries..."
+                                    }
+                                }
+                            }
                         }
                     }
                 }
             }
-            div class="grid grid-cols-1 justify-items-center p-4" {
-                div class="p-4" {
-                    article class="prose" {
-                        h2 {
-                            (entry.title)
-                        }
-                        h5 {
-                            (entry.pub_date)
-                        }
-                        (PreEscaped(cleaned))
-                    }
-                }
+        }
+    })
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq)]
+enum EntriesVisibility {
+    #[default]
+    #[serde(rename = "unread")]
+    Unread,
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "all")]
+    All,
+}
+
+impl EntriesVisibility {
+    fn is_read(&self) -> bool {
+        *self == Self::Read
+    }
+
+    fn is_all(&self) -> bool {
+        *self == Self::All
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntriesVisibility::Unread => "unread",
+            EntriesVisibility::Read => "read",
+            EntriesVisibility::All => "all",
+        }
+    }
+
+    /// the condition on `entries.read_at` for this visibility,
+    /// to be pushed onto a `where` clause
+    fn read_at_condition(&self) -> &'static str {
+        match self {
+            EntriesVisibility::Unread => " and entries.read_at is null ",
+            EntriesVisibility::Read => " and entries.read_at is not null ",
+            EntriesVisibility::All => "",
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn feed_show(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+    Query(params): Query<FeedShowParams>,
+) -> Result<impl IntoResponse, AppError> {
+    #[derive(FromRow)]
+    struct Feed {
+        title: String,
+        folder_id: Option<i64>,
+    }
+
+    #[derive(FromRow)]
+    struct Folder {
+        id: i64,
+        title: String,
+    }
+
+    #[derive(FromRow)]
+    struct Entry {
+        id: i64,
+        // an `Option` so that the next page's cursor can tell null from empty
+        title: Option<String>,
+        pub_date: String,
+        link: Option<String>,
+        read_at: Option<String>,
+        starred_at: Option<String>,
+    }
+
+    let mut conn = state.pool.acquire().await?;
+
+    let feed: Feed = sqlx::query_as(
+        "
+        select
+            title,
+            folder_id
+        from feeds
+        where id = ?",
+    )
+    .bind(feed_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let folders: Vec<Folder> = sqlx::query_as(
+        "
+        select
+            id,
+            title
+        from folders
+        order by title asc",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut entries: Vec<Entry> = {
+        let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+            "
+        select
+            id,
+            title,
+            pub_date,
+            link,
+            read_at,
+            starred_at
+        from entries
+        where feed_id = ",
+        );
+
+        qb.push_bind(feed_id);
+
+        qb.push(
+            params
+                .entries_visibility
+                .unwrap_or_default()
+                .read_at_condition(),
+        );
+
+        params.cursor.push_page(&mut qb, params.sort);
+
+        qb.build_query_as().fetch_all(&mut *conn).await?
+    };
+
+    let next_page = if entries.len() as i64 > ENTRIES_PAGE_SIZE {
+        entries.pop();
+        entries.last().map(|entry| match params.sort.column() {
+            EntriesSortColumn::Title => EntriesCursor::after(entry.title.as_deref(), entry.id),
+            EntriesSortColumn::PubDate => EntriesCursor::after_pub_date(&entry.pub_date, entry.id),
+            EntriesSortColumn::ReadAt => EntriesCursor::after(entry.read_at.as_deref(), entry.id),
+        })
+    } else {
+        None
+    };
+
+    let entries_visibility = params.entries_visibility.unwrap_or_default();
+
+    let sort_query = params.sort.query_string();
+
+    let sort_href = format!(
+        "/feeds/{feed_id}?entries_visibility={}&",
+        entries_visibility.as_str()
+    );
+
+    Ok(layout! {
+        html! {
+            div class="breadcrumbs text-sm" {
+                ul {
+                    li {
+                        a href="/" {
+                            "Feeds"
+                        }
+                    }
+                    li {
+                        a href=(format!("/feeds/{}", feed_id)) {
+                            (feed.title)
+                        }
+                    }
+                }
+            }
+            div class="p-2" {
+                header class="flex flex-wrap justify-start" {
+                    h1 {
+                        (feed.title)
+                    }
+                    div {
+                        a
+                            id="refresher"
+                            class="link p-2"
+                            hx-put=(format!("/feeds/{feed_id}/refresh"))
+                            // the parent div is the target
+                            hx-target="closest :not(#refresher)"
+                            // so we append "afterend" the parent div
+                            hx-swap="afterend"
+                        {
+                            "Refresh feed"
+                        }
+                    }
+                    div {
+                        a
+                            class="link p-2"
+                            hx-delete=(format!("/feeds/{feed_id}"))
+                            hx-confirm=(format!("Unsubscribe from {} and delete all of its entries? Feeds with starred entries can't be deleted.", feed.title))
+                            hx-swap="none"
+                        {
+                            "Delete feed"
+                        }
+                    }
+                    div {
+                        select
+                            class="select select-sm w-auto"
+                            name="folder_id"
+                            aria-label="Folder"
+                            hx-put=(format!("/feeds/{feed_id}/folder"))
+                            hx-trigger="change"
+                            hx-swap="none"
+                        {
+                            option value="" selected[feed.folder_id.is_none()] { "No folder" }
+                            @for folder in &folders {
+                                option value=(folder.id) selected[feed.folder_id == Some(folder.id)] {
+                                    (folder.title)
+                                }
+                            }
+                        }
+                    }
+                    // class=ml-auto here is a hack to get things to go to the right
+                    // there is probably a better way to do this,
+                    // will probably reevaluate this nav functionality entirely
+                    div class="ml-auto" {
+                        @match params.entries_visibility {
+                            Some(v) => {
+                                @match v {
+                                    EntriesVisibility::Unread => {
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read&{sort_query}")) {
+                                            "View read entries"
+                                        }
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all&{sort_query}")) {
+                                            "View all entries"
+                                        }
+                                    },
+                                    EntriesVisibility::Read => {
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=unread&{sort_query}")) {
+                                            "View unread entries"
+                                        }
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all&{sort_query}")) {
+                                            "View all entries"
+                                        }
+                                    },
+                                    EntriesVisibility::All => {
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=unread&{sort_query}")) {
+                                            "View unread entries"
+                                        }
+                                        a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read&{sort_query}")) {
+                                            "View read entries"
+                                        }
+                                    },
+                                }
+                            },
+                            None => {
+                                a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=read&{sort_query}")) {
+                                    "View read entries"
+                                }
+                                a class="link p-2" href=(format!("/feeds/{feed_id}?entries_visibility=all&{sort_query}")) {
+                                    "View all entries"
+                                }
+                            },
+                        }
+                    }
+                }
+                (mark_read_form(&format!("/feeds/{feed_id}/mark_read")))
+                main
+                    id="entries-list"
+                    hx-get=(format!(
+                        "/feeds/{feed_id}?entries_visibility={}&{sort_query}",
+                        entries_visibility.as_str()
+                    ))
+                    hx-trigger="entriesRead from:body"
+                    hx-select="#entries-list"
+                    hx-swap="outerHTML"
+                    // the star links and the "load more" button do their own swaps
+                    hx-disinherit="*"
+                {
+                    form id="entries" {
+                        div class="flex flex-wrap gap-2" {
+                            button
+                                class="btn btn-sm"
+                                type="button"
+                                hx-post=(format!("/feeds/{feed_id}/entries"))
+                                hx-vals=r#"{"action": "mark_read"}"#
+                            {
+                                "Mark selected read"
+                            }
+                            button
+                                class="btn btn-sm"
+                                type="button"
+                                hx-post=(format!("/feeds/{feed_id}/entries"))
+                                hx-vals=r#"{"action": "mark_unread"}"#
+                            {
+                                "Mark selected unread"
+                            }
+                            button
+                                class="btn btn-sm"
+                                type="button"
+                                hx-post=(format!("/feeds/{feed_id}/entries"))
+                                hx-vals=r#"{"action": "delete"}"#
+                                hx-confirm="Delete the selected entries? Starred entries are kept."
+                            {
+                                "Delete selected"
+                            }
+                        }
+                        table class="table" {
+                            thead {
+                                tr {
+                                    th {
+                                        input
+                                            class="checkbox checkbox-sm"
+                                            type="checkbox"
+                                            aria-label="Select all"
+                                            onclick="document.querySelectorAll('#entries input[name=entry_ids]').forEach((c) => c.checked = this.checked)";
+                                    }
+                                    th { (sort_header("Title", &sort_href, params.sort, EntriesSortColumn::Title)) }
+                                    th class="hidden sm:table-cell" {
+                                        (sort_header("Publication date", &sort_href, params.sort, EntriesSortColumn::PubDate))
+                                    }
+                                    @if params.entries_visibility.map(|v| v.is_read() || v.is_all()).unwrap_or(false) {
+                                        th class="hidden sm:table-cell" {
+                                            (sort_header("Read at", &sort_href, params.sort, EntriesSortColumn::ReadAt))
+                                        }
+                                    }
+                                    th class="hidden sm:table-cell" { "" }
+                                    th { "" }
+                                }
+                            }
+                            tbody {
+                                @for entry in entries {
+                                    tr data-entry-id=(entry.id) {
+                                        td {
+                                            input
+                                                class="checkbox checkbox-sm"
+                                                type="checkbox"
+                                                name="entry_ids"
+                                                value=(entry.id);
+                                        }
+                                        td class="text-center sm:text-left" {
+                                            a class="link" href=(format!("/entries/{}?entries_visibility={}", entry.id, entries_visibility.as_str())) {
+                                                (entry.title.unwrap_or_else(String::new))
+                                            }
+                                        }
+                                        td class="hidden sm:table-cell" { (entry.pub_date) }
+                                        @if params.entries_visibility.map(|v| v.is_read() || v.is_all()).unwrap_or(false) {
+                                            td class="hidden sm:table-cell" { (entry.read_at.unwrap_or_else(String::new)) }
+                                        }
+                                        td class="hidden sm:table-cell" {
+                                            @if let Some(link) = entry.link {
+                                                a
+                                                    class="link"
+                                                    href=(link)
+                                                    target="_blank"
+                                                {
+                                                    "View original"
+                                                }
+                                            }
+                                        }
+                                        td {
+                                            a
+                                                class="link"
+                                                hx-put=(format!("/entries/{}?action=toggle_starred", entry.id))
+                                                hx-swap="innerHTML"
+                                            {
+                                                @if entry.starred_at.is_some() {
+                                                    "Unstar"
+                                                } @else {
+                                                    "Star"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                // replaced by the next page's rows
+                                @if let Some(next_page) = next_page {
+                                    tr {
+                                        td colspan="6" {
+                                            button
+                                                class="btn btn-sm"
+                                                type="button"
+                                                hx-get=(format!(
+                                                    "/feeds/{feed_id}?entries_visibility={}&{sort_query}&{}",
+                                                    entries_visibility.as_str(),
+                                                    next_page.query_string()
+                                                ))
+                                                hx-target="closest tr"
+                                                hx-select="#entries tbody > tr"
+                                                hx-swap="outerHTML"
+                                            {
+                                                "Load more"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[instrument(skip(state))]
+async fn entry_show(
+    State(state): State<AppState>,
+    Path(entry_id): Path<i64>,
+    Query(params): Query<EntryShowParams>,
+) -> Result<impl IntoResponse, AppError> {
+    #[derive(FromRow)]
+    struct Entry {
+        feed_id: i64,
+        title: String,
+        // author: String,
+        description: String,
+        content: String,
+        pub_date: String,
+        link: Option<String>,
+        read_at: Option<String>,
+        starred_at: Option<String>,
+        updated_since_read: bool,
+    }
+
+    #[derive(FromRow)]
+    struct Feed {
+        title: String,
+    }
+
+    let mut conn = state.pool.acquire().await?;
+
+    if state.mark_read == MarkRead::Open {
+        mark_entry_read(&mut conn, entry_id).await?;
+    }
+
+    let entry: Entry = sqlx::query_as(
+        "
+        select
+            feed_id,
+            title,
+            author,
+            description,
+            content,
+            pub_date,
+            link,
+            read_at,
+            starred_at,
+            read_at is not null
+                and julianday(updated_at) > julianday(read_at) as updated_since_read
+        from entries
+        where id = ?
+        ",
+    )
+    .bind(entry_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let feed: Feed = sqlx::query_as(
+        "
+        select
+            title
+        from feeds
+        where id = ?
+        ",
+    )
+    .bind(entry.feed_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let entries_visibility = params.entries_visibility.unwrap_or_default();
+
+    // the entries on either side of this one in feed_show's default order,
+    // newest first
+    let here = EntriesCursor::after_pub_date(&entry.pub_date, entry_id);
+
+    let mut neighbor = async |dir| -> Result<Option<i64>, sqlx::Error> {
+        let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+            "
+        select
+            entries.id
+        from entries
+        where entries.feed_id = ",
+        );
+
+        qb.push_bind(entry.feed_id);
+
+        qb.push(entries_visibility.read_at_condition());
+
+        here.push_page(
+            &mut qb,
+            Sort {
+                sort: Some(EntriesSortColumn::PubDate),
+                dir: Some(dir),
+            },
+        );
+
+        qb.build_query_scalar().fetch_optional(&mut *conn).await
+    };
+
+    let previous_entry_id = neighbor(SortDirection::Asc).await?;
+    let next_entry_id = neighbor(SortDirection::Desc).await?;
+
+    let content = if entry.content.len() >= entry.description.len() {
+        entry.content
+    } else {
+        entry.description
+    };
+
+    let cleaned = ammonia::clean(&content);
+
+    Ok(layout! {
+        html! {
+            div class="breadcrumbs text-sm" {
+                ul {
+                    li {
+                        a href="/" {
+                            "Feeds"
+                        }
+                    }
+                    li {
+                        a href=(format!("/feeds/{}", entry.feed_id)) {
+                            (feed.title)
+                        }
+                    }
+                    li {
+                        a href=(format!("/entries/{}", entry_id)) {
+                            (entry.title)
+                        }
+                    }
+                }
+            }
+            div class="grid grid-cols-1 justify-items-center p-4" {
+                div class="p-4" {
+                    article class="prose" {
+                        h2 {
+                            (entry.title)
+                        }
+                        h5 {
+                            (entry.pub_date)
+                        }
+                        @if entry.updated_since_read {
+                            div class="badge badge-info" {
+                                "Updated since you read it"
+                            }
+                        }
+                        (PreEscaped(cleaned))
+                    }
+                }
+
+                @if state.mark_read == MarkRead::Scroll && entry.read_at.is_none() {
+                    // marks the entry read once the reader gets to the end of it
+                    div
+                        class="divider"
+                        hx-put=(format!("/entries/{}?action=mark_read", entry_id))
+                        hx-trigger="revealed"
+                        hx-target="#read-toggle"
+                        hx-swap="innerHTML"
+                    {}
+                } @else {
+                    div class="divider" {}
+                }
+
+                div {
+                    a class="link p-2" href=(format!("/feeds/{}?entries_visibility={}", entry.feed_id, entries_visibility.as_str())) {
+                        "Back"
+                    }
+                    @if let Some(previous_entry_id) = previous_entry_id {
+                        a id="previous-entry" class="link p-2" href=(format!("/entries/{previous_entry_id}?entries_visibility={}", entries_visibility.as_str())) {
+                            "Previous"
+                        }
+                    }
+                    @if let Some(next_entry_id) = next_entry_id {
+                        a id="next-entry" class="link p-2" href=(format!("/entries/{next_entry_id}?entries_visibility={}", entries_visibility.as_str())) {
+                            "Next"
+                        }
+                        a
+                            class="link p-2"
+                            hx-put=(format!("/entries/{}?action=mark_read", entry_id))
+                            hx-swap="none"
+                            hx-on::after-request=(format!(
+                                "if (event.detail.successful) window.location.href = '/entries/{next_entry_id}?entries_visibility={}'",
+                                entries_visibility.as_str()
+                            ))
+                        {
+                            "Mark read and next"
+                        }
+                    }
+                    @if entry.read_at.is_none() {
+                        a
+                            id="read-toggle"
+                            class="link p-2"
+                            hx-put=(format!("/entries/{}?action=toggle_read_unread", entry_id))
+                            hx-swap="innerHTML"
+                        {
+                            "Mark read"
+                        }
+                    } @else {
+                        a
+                            id="read-toggle"
+                            class="link p-2"
+                            hx-put=(format!("/entries/{}?action=toggle_read_unread", entry_id))
+                            hx-swap="innerHTML"
+                        {
+                            "Mark unread"
+                        }
+                    }
+                    a
+                        class="link p-2"
+                        hx-put=(format!("/entries/{}?action=toggle_starred", entry_id))
+                        hx-swap="innerHTML"
+                    {
+                        @if entry.starred_at.is_some() {
+                            "Unstar"
+                        } @else {
+                            "Star"
+                        }
+                    }
+                    @if let Some(link) = entry.link {
+                        a
+                            id="original-link"
+                            class="link p-2"
+                            href=(link)
+                            target="_blank"
+                        {
+                            "View original"
+                        }
+                    }
+                    a
+                        class="link p-2"
+                        hx-delete=(format!("/entries/{}", entry_id))
+                        hx-confirm="Delete this entry?"
+                        hx-swap="none"
+                    {
+                        "Delete"
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// marks an entry read, keeping the original read_at if it was already read
+async fn mark_entry_read(
+    conn: &mut sqlx::SqliteConnection,
+    entry_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "
+        update entries
+        set read_at = coalesce(read_at, ?1)
+        where id = ?2
+        ",
+    )
+    .bind(chrono::Utc::now())
+    .bind(entry_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+struct EntryShowParams {
+    entries_visibility: Option<EntriesVisibility>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EntryUpdateParams {
+    action: EntryUpdateAction,
+}
+
+#[derive(Deserialize, Debug)]
+enum EntryUpdateAction {
+    #[serde(rename = "refresh")]
+    Refresh,
+    #[serde(rename = "toggle_read_unread")]
+    ToggleReadUnread,
+    #[serde(rename = "toggle_starred")]
+    ToggleStarred,
+    #[serde(rename = "mark_read")]
+    MarkRead,
+}
+
+#[instrument(skip(state))]
+async fn entry_update(
+    State(state): State<AppState>,
+    Path(entry_id): Path<i64>,
+    Query(params): Query<EntryUpdateParams>,
+) -> Result<impl IntoResponse, AppError> {
+    match params.action {
+        EntryUpdateAction::Refresh => {
+            let mut conn = state.pool.acquire().await?;
+
+            let (_read_at,): (String,) = sqlx::query_as(
+                "
+                update entries
+                set read_at = ?1
+                where entry_id = ?2
+                returning read_at
+                ",
+            )
+            .bind(chrono::Utc::now())
+            .bind(entry_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            Ok(html! {
+                "ok"
+            })
+        }
+        EntryUpdateAction::ToggleReadUnread => {
+            let read = toggle_entry_timestamp(&state.pool, entry_id, "read_at").await?;
+
+            Ok(html! {
+                @if read { "Mark unread" } @else { "Mark read" }
+            })
+        }
+        EntryUpdateAction::MarkRead => {
+            let mut conn = state.pool.acquire().await?;
+
+            mark_entry_read(&mut conn, entry_id).await?;
+
+            Ok(html! {
+                "Mark unread"
+            })
+        }
+        EntryUpdateAction::ToggleStarred => {
+            let starred = toggle_entry_timestamp(&state.pool, entry_id, "starred_at").await?;
+
+            Ok(html! {
+                @if starred { "Unstar" } @else { "Star" }
+            })
+        }
+    }
+}
+
+/// sets a nullable timestamp column like `read_at` to now if it is null,
+/// or back to null if it isn't.
+/// returns whether it is now set.
+/// `column` is interpolated into the query, so it must never come from user input.
+async fn toggle_entry_timestamp(
+    pool: &sqlx::Pool<Sqlite>,
+    entry_id: i64,
+    column: &'static str,
+) -> Result<bool, sqlx::Error> {
+    let (is_set,): (bool,) = sqlx::query_as(&format!(
+        "
+        update entries
+        set {column} = case when {column} is null then ?1 else null end
+        where id = ?2
+        returning {column} is not null
+        "
+    ))
+    .bind(chrono::Utc::now())
+    .bind(entry_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(is_set)
+}
+
+#[instrument(skip(state))]
+async fn starred_index(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    #[derive(FromRow)]
+    struct Entry {
+        id: i64,
+        title: String,
+        feed_id: i64,
+        feed_title: String,
+        pub_date: String,
+        starred_at: String,
+    }
+
+    let mut conn = state.pool.acquire().await?;
+
+    let entries: Vec<Entry> = sqlx::query_as(
+        "
+    select
+        entries.id,
+        entries.title,
+        entries.feed_id,
+        feeds.title as feed_title,
+        entries.pub_date,
+        entries.starred_at
+    from entries
+    inner join feeds
+        on feeds.id = entries.feed_id
+    where entries.starred_at is not null
+    order by entries.starred_at desc
+    ",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(layout! {
+        html! {
+            div class="breadcrumbs text-sm" {
+                ul {
+                    li {
+                        a href="/" {
+                            "Feeds"
+                        }
+                    }
+                    li {
+                        a href="/starred" {
+                            "Starred"
+                        }
+                    }
+                }
+            }
+            div class="p-2" {
+                table class="table" {
+                    thead {
+                        tr {
+                            th { "Title" }
+                            th class="hidden sm:table-cell" { "Feed" }
+                            th class="hidden sm:table-cell" { "Publication date" }
+                            th class="hidden sm:table-cell" { "Starred at" }
+                            th { "" }
+                        }
+                    }
+                    tbody {
+                        @for entry in entries {
+                            tr {
+                                td class="text-center sm:text-left" {
+                                    a class="link" href=(format!("/entries/{}", entry.id)) {
+                                        (entry.title)
+                                    }
+                                }
+                                td class="hidden sm:table-cell" {
+                                    a class="link" href=(format!("/feeds/{}", entry.feed_id)) {
+                                        (entry.feed_title)
+                                    }
+                                }
+                                td class="hidden sm:table-cell" { (entry.pub_date) }
+                                td class="hidden sm:table-cell" { (entry.starred_at) }
+                                td {
+                                    a
+                                        class="link"
+                                        hx-put=(format!("/entries/{}?action=toggle_starred", entry.id))
+                                        hx-swap="innerHTML"
+                                    {
+                                        "Unstar"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// marks entries read in bulk, optionally only the ones older than a cutoff
+fn mark_read_form(action: &str) -> maud::Markup {
+    html! {
+        form
+            class="flex flex-wrap items-center gap-2 py-2"
+            hx-post=(action)
+            hx-swap="afterend"
+            hx-confirm="Mark these entries read?"
+        {
+            select class="select select-sm w-auto" name="older_than_days" aria-label="Which entries" {
+                option value="" { "All entries" }
+                option value="1" { "Older than 1 day" }
+                option value="7" { "Older than 1 week" }
+            }
+            input
+                class="input input-sm w-auto"
+                type="date"
+                name="before"
+                aria-label="Or published before";
+            button class="btn btn-sm" type="submit" {
+                "Mark all read"
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct MarkReadParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    older_than_days: Option<i64>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    before: Option<chrono::NaiveDate>,
+}
+
+impl MarkReadParams {
+    /// only entries published before this get marked read.
+    /// when both are given, the earlier one wins.
+    /// `None` when `older_than_days` is negative or too far back to represent.
+    fn cutoff(&self) -> Option<Option<chrono::DateTime<chrono::Utc>>> {
+        let older_than = match self.older_than_days {
+            Some(days) if days >= 0 => Some(
+                chrono::TimeDelta::try_days(days)
+                    .and_then(|days| chrono::Utc::now().checked_sub_signed(days))?,
+            ),
+            Some(_) => return None,
+            None => None,
+        };
+
+        let before = self
+            .before
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|datetime| datetime.and_utc());
+
+        Some(match (older_than, before) {
+            (Some(older_than), Some(before)) => Some(older_than.min(before)),
+            (older_than, before) => older_than.or(before),
+        })
+    }
+}
+
+#[instrument(skip(state))]
+async fn feed_mark_read(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+    axum::Form(params): axum::Form<MarkReadParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool;
+
+    let Some(cutoff) = params.cutoff() else {
+        return Ok((StatusCode::BAD_REQUEST, "older_than_days is out of range").into_response());
+    };
+
+    let marked = mark_entries_read(&pool, Some(feed_id), cutoff).await?;
+
+    Ok(marked_read_response(marked).into_response())
+}
+
+#[instrument(skip(state))]
+async fn feeds_mark_read(
+    State(state): State<AppState>,
+    axum::Form(params): axum::Form<MarkReadParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool;
+
+    let Some(cutoff) = params.cutoff() else {
+        return Ok((StatusCode::BAD_REQUEST, "older_than_days is out of range").into_response());
+    };
+
+    let marked = mark_entries_read(&pool, None, cutoff).await?;
+
+    Ok(marked_read_response(marked).into_response())
+}
+
+/// mark every unread entry read, in one feed or all of them,
+/// optionally only the ones published before `cutoff`.
+/// returns how many were marked read.
+async fn mark_entries_read(
+    pool: &sqlx::Pool<Sqlite>,
+    feed_id: Option<i64>,
+    cutoff: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<u64, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+        "
+        update entries
+        set read_at = ",
+    );
+    qb.push_bind(chrono::Utc::now());
+    qb.push(" where read_at is null ");
+
+    if let Some(feed_id) = feed_id {
+        qb.push(" and feed_id = ");
+        qb.push_bind(feed_id);
+    }
+
+    if let Some(cutoff) = cutoff {
+        qb.push(" and julianday(coalesce(pub_date, inserted_at)) < julianday(");
+        qb.push_bind(cutoff);
+        qb.push(") ");
+    }
+
+    let result = qb.build().execute(&mut *tx).await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// tells the entry lists and counts on the page to reload themselves
+fn marked_read_response(marked: u64) -> impl IntoResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Trigger", "entriesRead".parse().unwrap());
+
+    (
+        headers,
+        flash(html! {
+            (format!("marked {marked} entries read"))
+        }),
+    )
+}
+
+#[instrument(skip(state))]
+async fn entry_delete(
+    State(state): State<AppState>,
+    Path(entry_id): Path<i64>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = state.pool.acquire().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let (feed_id,): (i64,) = sqlx::query_as(
+        "
+        select
+            feed_id
+        from entries
+        where id = ?",
+    )
+    .bind(entry_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    delete_entries(&mut tx, feed_id, &[entry_id]).await?;
+
+    tx.commit().await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Location", format!("/feeds/{feed_id}").parse().unwrap());
+    Ok((headers, ""))
+}
+
+#[derive(Deserialize, Debug)]
+struct EntriesUpdateForm {
+    action: EntriesUpdateAction,
+    #[serde(default)]
+    entry_ids: Vec<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+enum EntriesUpdateAction {
+    #[serde(rename = "mark_read")]
+    MarkRead,
+    #[serde(rename = "mark_unread")]
+    MarkUnread,
+    #[serde(rename = "delete")]
+    Delete,
+}
+
+/// bulk actions on the entries selected in feed_show
+#[instrument(skip(state, body))]
+async fn entries_update(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+    body: axum::body::Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    // not axum's `Form`, which can't do repeated `entry_ids` keys
+    let form: EntriesUpdateForm = match serde_html_form::from_bytes(&body) {
+        Ok(form) => form,
+        Err(e) => return Ok((StatusCode::BAD_REQUEST, e.to_string()).into_response()),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Refresh", "true".parse().unwrap());
+
+    if form.entry_ids.is_empty() {
+        return Ok((headers, "").into_response());
+    }
+
+    let mut conn = state.pool.acquire().await?;
+
+    let mut tx = conn.begin().await?;
+
+    match form.action {
+        EntriesUpdateAction::MarkRead | EntriesUpdateAction::MarkUnread => {
+            let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new("update entries ");
+
+            if let EntriesUpdateAction::MarkRead = form.action {
+                qb.push(" set read_at = ");
+                qb.push_bind(chrono::Utc::now());
+                qb.push(" where read_at is null ");
+            } else {
+                qb.push(" set read_at = null where read_at is not null ");
+            }
+
+            qb.push(" and feed_id = ");
+            qb.push_bind(feed_id);
+            qb.push(" and id in (");
+            let mut separated = qb.separated(", ");
+            for entry_id in &form.entry_ids {
+                separated.push_bind(entry_id);
+            }
+            separated.push_unseparated(")");
+
+            qb.build().execute(&mut *tx).await?;
+        }
+        EntriesUpdateAction::Delete => {
+            // bulk deletion is for clearing out a feed,
+            // starred entries are kept unless deleted one at a time
+            let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+                "
+                select
+                    id
+                from entries
+                where starred_at is null
+                and id in (",
+            );
+            let mut separated = qb.separated(", ");
+            for entry_id in &form.entry_ids {
+                separated.push_bind(entry_id);
+            }
+            separated.push_unseparated(")");
+
+            let entry_ids: Vec<i64> = qb
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .map(|(entry_id,): (i64,)| entry_id)
+                .collect();
+
+            if !entry_ids.is_empty() {
+                delete_entries(&mut tx, feed_id, &entry_ids).await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok((headers, "").into_response())
+}
+
+/// delete entries, leaving tombstones in `deleted_entries`
+/// so the next refresh doesn't bring them back
+async fn delete_entries(
+    conn: &mut sqlx::SqliteConnection,
+    feed_id: i64,
+    entry_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+        "
+        insert or ignore into deleted_entries (feed_id, guid, link)
+        select
+            feed_id,
+            guid,
+            link
+        from entries
+        where feed_id = ",
+    );
+    qb.push_bind(feed_id);
+    qb.push(" and id in (");
+    let mut separated = qb.separated(", ");
+    for entry_id in entry_ids {
+        separated.push_bind(entry_id);
+    }
+    separated.push_unseparated(")");
+
+    qb.build().execute(&mut *conn).await?;
+
+    let mut qb: sqlx::QueryBuilder<Sqlite> =
+        sqlx::QueryBuilder::new("delete from entries where feed_id = ");
+    qb.push_bind(feed_id);
+    qb.push(" and id in (");
+    let mut separated = qb.separated(", ");
+    for entry_id in entry_ids {
+        separated.push_bind(entry_id);
+    }
+    separated.push_unseparated(")");
+
+    qb.build().execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchParams {
+    q: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    feed_id: Option<i64>,
+    entries_visibility: Option<EntriesVisibility>,
+}
+
+/// an unselected `select` in a form is submitted as an empty string,
+/// which should mean "no value" rather than fail to parse
+fn empty_string_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[instrument(skip(state))]
+async fn search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, AppError> {
+    #[derive(FromRow)]
+    struct Feed {
+        id: i64,
+        title: String,
+    }
+
+    #[derive(FromRow)]
+    struct SearchResult {
+        id: i64,
+        title: String,
+        feed_title: String,
+        pub_date: String,
+        snippet: String,
+    }
+
+    // searching usually means looking for something already read
+    let entries_visibility = params.entries_visibility.unwrap_or(EntriesVisibility::All);
+
+    let q = params.q.as_deref().map(str::trim).unwrap_or("");
+
+    let mut conn = state.pool.acquire().await?;
+
+    let feeds: Vec<Feed> = sqlx::query_as(
+        "
+    select
+        id,
+        title
+    from feeds
+    order by title asc",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let results: Vec<SearchResult> = if q.is_empty() {
+        vec![]
+    } else {
+        let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(
+            "
+        select
+            entries.id,
+            entries.title,
+            feeds.title as feed_title,
+            entries.pub_date,
+            snippet(entries_search, -1, char(2), char(3), '…', 24) as snippet
+        from entries_search
+        inner join entries
+            on entries.id = entries_search.rowid
+        inner join feeds
+            on feeds.id = entries.feed_id
+        where entries_search match ",
+        );
+
+        qb.push_bind(fts5_query(q));
+
+        if let Some(feed_id) = params.feed_id {
+            qb.push(" and entries.feed_id = ");
+            qb.push_bind(feed_id);
+        }
+
+        qb.push(entries_visibility.read_at_condition());
+
+        qb.push(" order by entries_search.rank limit 100 ");
+
+        qb.build_query_as().fetch_all(&mut *conn).await?
+    };
+
+    Ok(layout! {
+        html! {
+            div class="breadcrumbs text-sm" {
+                ul {
+                    li {
+                        a href="/" {
+                            "Feeds"
+                        }
+                    }
+                    li {
+                        a href="/search" {
+                            "Search"
+                        }
+                    }
+                }
+            }
+            div class="p-2" {
+                form class="flex flex-wrap gap-2 py-4" action="/search" method="get" {
+                    input
+                        class="input"
+                        type="search"
+                        name="q"
+                        placeholder="Search entries"
+                        value=(q)
+                        autofocus;
+                    select class="select w-auto" name="feed_id" {
+                        option value="" { "All feeds" }
+                        @for feed in &feeds {
+                            option value=(feed.id) selected[params.feed_id == Some(feed.id)] {
+                                (feed.title)
+                            }
+                        }
+                    }
+                    select class="select w-auto" name="entries_visibility" {
+                        option value="all" selected[entries_visibility.is_all()] { "All entries" }
+                        option value="unread" selected[entries_visibility == EntriesVisibility::Unread] { "Unread entries" }
+                        option value="read" selected[entries_visibility.is_read()] { "Read entries" }
+                    }
+                    button class="btn" type="submit" {
+                        "Search"
+                    }
+                }
+                @if !q.is_empty() {
+                    p class="text-sm" {
+                        (format!("{} results", results.len()))
+                    }
+                }
+                ul {
+                    @for result in results {
+                        li class="py-2" {
+                            a class="link" href=(format!("/entries/{}", result.id)) {
+                                (result.title)
+                            }
+                            div class="text-sm" {
+                                (result.feed_title)
+                                " · "
+                                (result.pub_date)
+                            }
+                            p {
+                                (highlighted(&result.snippet))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// turn what someone typed into an FTS5 query that can't be a syntax error:
+/// every whitespace-separated term becomes a quoted string, all of which must match
+fn fts5_query(q: &str) -> String {
+    q.split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// render a snippet from `search`, where matches are delimited by
+/// `\u{2}` and `\u{3}`, as escaped text with `mark`ed matches
+fn highlighted(snippet: &str) -> maud::Markup {
+    html! {
+        @for (i, part) in snippet.split(['\u{2}', '\u{3}']).enumerate() {
+            @if i % 2 == 1 {
+                mark { (part) }
+            } @else {
+                (part)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum FeedCreateError {
+    #[error("bad input")]
+    BadInput(&'static str),
+    #[error("feed already exists")]
+    AlreadyExists,
+    #[error("network error")]
+    NetworkError(#[from] reqwest::Error),
+    #[error("feed parse error")]
+    FeedParseError(#[from] feed_rs::parser::ParseFeedError),
+    #[error("database error")]
+    DatabaseError(#[from] sqlx::Error),
+}
+
+impl FeedCreateError {
+    fn status_code_and_message(self) -> (StatusCode, String) {
+        match self {
+            FeedCreateError::BadInput(s) => (StatusCode::BAD_REQUEST, s.to_string()),
+            FeedCreateError::AlreadyExists => {
+                (StatusCode::BAD_REQUEST, "Feed already exists".to_string())
+            }
+            FeedCreateError::NetworkError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to fetch remote feed: {e}"),
+            ),
+            FeedCreateError::FeedParseError(e) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Could not parse feed: {e}"),
+            ),
+            FeedCreateError::DatabaseError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {e}"),
+            ),
+        }
+    }
+}
+
+async fn do_feed_create(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<(), FeedCreateError> {
+    let feed = headers.get("HX-Prompt").ok_or(FeedCreateError::BadInput(
+        "somehow the HX-Prompt header did not get included",
+    ))?;
+
+    let s = feed
+        .to_str()
+        .map_err(|_| FeedCreateError::BadInput("could not convert HX-Prompt value to str"))?;
+
+    let feed_url =
+        Url::parse(s).map_err(|_| FeedCreateError::BadInput("could not parse str as URL"))?;
+
+    let pool = state.pool;
+    let http_client = state.http_client;
+
+    create_feed(&pool, &http_client, &feed_url).await?;
+
+    Ok(())
+}
+
+/// fetch, parse, and insert a feed and all of its current entries,
+/// returning the id of the new feed
+async fn create_feed(
+    pool: &sqlx::Pool<Sqlite>,
+    http_client: &reqwest::Client,
+    feed_url: &Url,
+) -> Result<i64, FeedCreateError> {
+    let already_exists: Option<(bool,)> = sqlx::query_as(
+        "
+    select
+        1
+    from feeds
+    where feed_link = ?",
+    )
+    .bind(feed_url.as_str())
+    .fetch_optional(pool)
+    .await?;
+
+    if already_exists.is_some() {
+        return Err(FeedCreateError::AlreadyExists);
+    }
+
+    let response = http_client
+        .get(feed_url.clone())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let (etag, last_modified) = caching_headers(response.headers());
+
+    let body = response.bytes().await?;
+
+    let feed = parse_feed(&body)?;
+
+    let mut conn = pool.acquire().await?;
+
+    let mut tx = conn.begin().await?;
+
+    #[derive(FromRow)]
+    struct Feed {
+        id: i64,
+    }
+
+    let Feed { id: feed_id } = sqlx::query_as(
+        "
+        insert into feeds (title, link, feed_link, feed_kind, latest_etag, latest_last_modified)
+        values (?1, ?2, ?3, ?4, ?5, ?6)
+        returning id",
+    )
+    .bind(
+        feed.title
+            .as_ref()
+            .map(|title| title.content.as_str())
+            .unwrap_or(feed_url.as_str()),
+    )
+    .bind(feed.links.first().map(|link| &link.href))
+    .bind(feed_url.as_str())
+    .bind(match feed.feed_type {
+        feed_rs::model::FeedType::Atom => "Atom",
+        feed_rs::model::FeedType::JSON => "JSON",
+        feed_rs::model::FeedType::RSS0 => "RSS",
+        feed_rs::model::FeedType::RSS1 => "RSS",
+        feed_rs::model::FeedType::RSS2 => "RSS",
+    })
+    .bind(etag)
+    .bind(last_modified)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // someone else added it while we were fetching it
+        sqlx::Error::Database(e) if e.is_unique_violation() => FeedCreateError::AlreadyExists,
+        e => e.into(),
+    })?;
+
+    for entry in &feed.entries {
+        insert_entry(&mut tx, feed_id, entry).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(feed_id)
+}
+
+/// feed_rs makes up an id for entries that don't have one,
+/// hashed from the link and title or failing that a random UUID.
+/// neither is stable across refreshes, so missing ids are left empty
+/// for `entry_guid` to fall back to the link instead
+fn parse_feed(body: &[u8]) -> Result<feed_rs::model::Feed, feed_rs::parser::ParseFeedError> {
+    feed_rs::parser::Builder::new()
+        .id_generator(|_links, _title, _uri| String::new())
+        .build()
+        .parse(body)
+}
+
+/// the key an entry is deduplicated on within its feed:
+/// its id/guid, or its first link if it somehow has no id
+fn entry_guid(entry: &feed_rs::model::Entry) -> Option<&str> {
+    if !entry.id.is_empty() {
+        Some(&entry.id)
+    } else {
+        entry.links.first().map(|link| link.href.as_str())
+    }
+}
+
+/// insert an entry unless the feed already has an entry with the same guid.
+/// an entry with neither an id nor a link can't be told apart from
+/// the next refresh's copy of it, so it is skipped.
+/// returns whether it was inserted.
+async fn insert_entry(
+    conn: &mut sqlx::SqliteConnection,
+    feed_id: i64,
+    entry: &feed_rs::model::Entry,
+) -> Result<bool, sqlx::Error> {
+    let Some(guid) = entry_guid(entry) else {
+        return Ok(false);
+    };
+
+    let entry_id: Option<(i64,)> = sqlx::query_as(
+        "
+        insert into entries (feed_id, guid, title, author, pub_date, description, content, link, remote_updated_at)
+        values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        on conflict (feed_id, guid) do nothing
+        returning id
+        ",
+    )
+    .bind(feed_id)
+    .bind(guid)
+    .bind(entry.title.as_ref().map(|title| &title.content))
+    .bind(entry.authors.first().map(|author| &author.name))
+    .bind(entry.published)
+    .bind(entry.summary.as_ref().map(|summary| &summary.content))
+    .bind(entry.content.as_ref().map(|content| &content.body))
+    .bind(entry.links.first().map(|link| &link.href))
+    .bind(entry.updated)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((entry_id,)) = entry_id else {
+        return Ok(false);
+    };
+
+    index_entry_for_search(conn, entry_id).await?;
+
+    Ok(true)
+}
+
+/// update an existing entry if the publisher has changed its
+/// `updated` timestamp, title, summary, content, or author since we last saw it.
+/// returns whether it was updated.
+async fn update_entry(
+    conn: &mut sqlx::SqliteConnection,
+    feed_id: i64,
+    guid: &str,
+    entry: &feed_rs::model::Entry,
+) -> Result<bool, sqlx::Error> {
+    let title = entry.title.as_ref().map(|title| &title.content);
+    let author = entry.authors.first().map(|author| &author.name);
+    let content = entry.content.as_ref().map(|content| &content.body);
+    let description = entry.summary.as_ref().map(|summary| &summary.content);
+
+    let updated: Option<(i64,)> = sqlx::query_as(
+        "
+        update entries
+        set
+            title = ?1,
+            author = ?2,
+            content = ?3,
+            remote_updated_at = ?4,
+            description = ?5,
+            updated_at = ?6
+        where feed_id = ?7
+        and guid = ?8
+        and (
+            title is not ?1
+            or author is not ?2
+            or content is not ?3
+            or (remote_updated_at is not null and remote_updated_at is not ?4)
+            or (description is not null and description is not ?5)
+        )
+        returning id
+        ",
+    )
+    .bind(title)
+    .bind(author)
+    .bind(content)
+    .bind(entry.updated)
+    .bind(description)
+    .bind(chrono::Utc::now())
+    .bind(feed_id)
+    .bind(guid)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some((entry_id,)) = updated {
+        index_entry_for_search(conn, entry_id).await?;
+
+        return Ok(true);
+    }
+
+    // entries from before we stored the remote timestamp and summary
+    // get them filled in without counting as an update
+    let backfilled: Option<(i64,)> = sqlx::query_as(
+        "
+        update entries
+        set
+            remote_updated_at = coalesce(remote_updated_at, ?1),
+            description = coalesce(description, ?2)
+        where feed_id = ?3
+        and guid = ?4
+        and (remote_updated_at is null or description is null)
+        returning id
+        ",
+    )
+    .bind(entry.updated)
+    .bind(description)
+    .bind(feed_id)
+    .bind(guid)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some((entry_id,)) = backfilled {
+        index_entry_for_search(conn, entry_id).await?;
+    }
+
+    Ok(false)
+}
+
+/// (re)write an entry's row in the `entries_search` full-text index.
+/// content is indexed as plain text, so markup doesn't match searches
+/// or show up in snippets.
+async fn index_entry_for_search(
+    conn: &mut sqlx::SqliteConnection,
+    entry_id: i64,
+) -> Result<(), sqlx::Error> {
+    let (title, author, description, content): (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ) = sqlx::query_as(
+        "
+        select
+            title,
+            author,
+            description,
+            content
+        from entries
+        where id = ?
+        ",
+    )
+    .bind(entry_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // same choice as entry_show
+    let description = description.unwrap_or_default();
+    let content = content.unwrap_or_default();
+    let html = if content.len() >= description.len() {
+        content
+    } else {
+        description
+    };
+
+    let text = html2text::config::plain_no_decorate()
+        .string_from_read(html.as_bytes(), 1000)
+        .unwrap_or(html);
+
+    sqlx::query("delete from entries_search where rowid = ?")
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "
+        insert into entries_search (rowid, title, author, content)
+        values (?1, ?2, ?3, ?4)
+        ",
+    )
+    .bind(entry_id)
+    .bind(title)
+    .bind(author)
+    .bind(text)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn feed_create(
+    headers: HeaderMap,
+    state: State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    match do_feed_create(headers, state).await {
+        Ok(()) => {
+            let mut headers = HeaderMap::new();
+            headers.insert("HX-Location", "/".parse().unwrap());
+            Ok((headers, "").into_response())
+        }
+        Err(e) => {
+            let (status_code, error_message) = e.status_code_and_message();
+
+            let mut headers = HeaderMap::new();
+
+            headers.insert(
+                "HX-Trigger",
+                axum::Json(format!("{{\"feedCreateError\":\"{error_message}\"}}"))
+                    .parse()
+                    .unwrap(),
+            );
+
+            Ok((status_code, headers, "").into_response())
+        }
+    }
+}
+
+#[instrument]
+async fn feeds_import_show() -> impl IntoResponse {
+    layout! {
+        html! {
+            div class="breadcrumbs text-sm" {
+                ul {
+                    li {
+                        a href="/" {
+                            "Feeds"
+                        }
+                    }
+                    li {
+                        a href="/feeds/import" {
+                            "Import"
+                        }
+                    }
+                }
+            }
+            div class="p-4" {
+                h1 {
+                    "Import feeds from OPML"
+                }
+                form
+                    class="flex flex-wrap gap-2 py-4"
+                    hx-post="/feeds/import"
+                    hx-encoding="multipart/form-data"
+                    hx-target="#import-result"
+                    hx-disabled-elt="find button"
+                {
+                    input
+                        class="file-input"
+                        type="file"
+                        name="opml"
+                        accept=".opml,.xml,text/x-opml,application/xml,text/xml"
+                        required;
+                    button class="btn" type="submit" {
+                        "Import"
+                    }
+                }
+                div id="import-result" {}
+            }
+        }
+    }
+}
+
+enum FeedImportOutcome {
+    Added,
+    AlreadyExists,
+    Failed(String),
+}
+
+#[instrument(skip(state, multipart))]
+async fn feeds_import(
+    State(state): State<AppState>,
+    mut multipart: axum::extract::Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let mut opml = None;
+
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() == Some("opml") {
+            opml = Some(field.bytes().await?);
+        }
+    }
+
+    let Some(opml) = opml else {
+        return Ok(html! {
+            p class="text-error" { "No OPML file was uploaded" }
+        });
+    };
+
+    let feed_urls = match opml_feed_urls(&opml) {
+        Ok(feed_urls) => feed_urls,
+        Err(e) => {
+            return Ok(html! {
+                p class="text-error" { (format!("Could not parse OPML: {e}")) }
+            });
+        }
+    };
+
+    let pool = state.pool;
+    let http_client = state.http_client;
+    let concurrency = state.refresh_concurrency;
+
+    let outcomes = map_bounded(feed_urls, concurrency, move |feed_url| {
+        let pool = pool.clone();
+        let http_client = http_client.clone();
+
+        async move {
+            let outcome = match Url::parse(&feed_url) {
+                Ok(url) => match tokio::time::timeout(
+                    FEED_REFRESH_TIMEOUT,
+                    create_feed(&pool, &http_client, &url),
+                )
+                .await
+                {
+                    Ok(Ok(_feed_id)) => FeedImportOutcome::Added,
+                    Ok(Err(FeedCreateError::AlreadyExists)) => FeedImportOutcome::AlreadyExists,
+                    Ok(Err(e)) => FeedImportOutcome::Failed(e.status_code_and_message().1),
+                    Err(_) => FeedImportOutcome::Failed("timed out".to_string()),
+                },
+                Err(e) => FeedImportOutcome::Failed(format!("Not a valid URL: {e}")),
+            };
+
+            (feed_url, outcome)
+        }
+    })
+    .await;
+
+    let added: Vec<&str> = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, FeedImportOutcome::Added))
+        .map(|(feed_url, _)| feed_url.as_str())
+        .collect();
+
+    let already_existed: Vec<&str> = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, FeedImportOutcome::AlreadyExists))
+        .map(|(feed_url, _)| feed_url.as_str())
+        .collect();
+
+    let failed: Vec<(&str, &str)> = outcomes
+        .iter()
+        .filter_map(|(feed_url, outcome)| match outcome {
+            FeedImportOutcome::Failed(e) => Some((feed_url.as_str(), e.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    Ok(html! {
+        h2 { (format!("Added {} feeds", added.len())) }
+        ul {
+            @for feed_url in &added {
+                li { (feed_url) }
+            }
+        }
+        h2 { (format!("{} feeds already existed", already_existed.len())) }
+        ul {
+            @for feed_url in &already_existed {
+                li { (feed_url) }
+            }
+        }
+        h2 { (format!("{} feeds failed", failed.len())) }
+        ul {
+            @for (feed_url, e) in &failed {
+                li {
+                    (feed_url)
+                    ": "
+                    span class="text-error" { (e) }
+                }
+            }
+        }
+    })
+}
+
+/// every distinct `xmlUrl` in an OPML document, in document order.
+/// `outline` elements can be nested to any depth (folders),
+/// only the ones with an `xmlUrl` are feeds.
+fn opml_feed_urls(opml: &[u8]) -> Result<Vec<String>, quick_xml::Error> {
+    let mut reader = quick_xml::Reader::from_reader(opml);
+    let mut buf = vec![];
+    let mut seen = HashSet::new();
+    let mut feed_urls = vec![];
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            quick_xml::events::Event::Start(element) | quick_xml::events::Event::Empty(element)
+                if element.local_name().as_ref() == b"outline" =>
+            {
+                for attribute in element.attributes() {
+                    let attribute = attribute?;
+
+                    if attribute
+                        .key
+                        .local_name()
+                        .as_ref()
+                        .eq_ignore_ascii_case(b"xmlUrl")
+                    {
+                        let feed_url = attribute
+                            .decode_and_unescape_value(reader.decoder())?
+                            .trim()
+                            .to_string();
+
+                        if !feed_url.is_empty() && seen.insert(feed_url.clone()) {
+                            feed_urls.push(feed_url);
+                        }
+                    }
+                }
+            }
+            quick_xml::events::Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(feed_urls)
+}
+
+#[instrument(skip(state))]
+async fn feeds_export(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool;
+
+    // built in memory rather than streamed, so that a failure is a 500
+    // rather than a truncated document behind a 200.
+    // even a large subscription list is only a few hundred KB
+    let mut opml = vec![];
+
+    write_opml(&pool, &mut opml).await?;
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "text/x-opml; charset=utf-8",
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"r2.opml\"",
+            ),
+        ],
+        opml,
+    ))
+}
+
+/// write every feed as an OPML 2.0 document to `w`.
+/// the HTTP export writes into memory and the CLI into a temp file,
+/// so a failure partway never leaves a partial document behind
+async fn write_opml<W>(pool: &sqlx::Pool<Sqlite>, w: &mut W) -> anyhow::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    #[derive(FromRow)]
+    struct Feed {
+        title: Option<String>,
+        feed_link: String,
+        link: Option<String>,
+        feed_kind: Option<String>,
+    }
+
+    use quick_xml::escape::escape;
+
+    w.write_all(
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<opml version=\"2.0\">
+  <head>
+    <title>r2 subscriptions</title>
+    <dateCreated>{}</dateCreated>
+  </head>
+  <body>
+",
+            chrono::Utc::now().to_rfc2822()
+        )
+        .as_bytes(),
+    )
+    .await?;
+
+    let mut feeds = sqlx::query_as::<_, Feed>(
+        "
+    select
+        title,
+        feed_link,
+        link,
+        feed_kind
+    from feeds
+    order by title asc",
+    )
+    .fetch(pool);
+
+    while let Some(feed) = feeds.try_next().await? {
+        let title = escape(feed.title.as_deref().unwrap_or(&feed.feed_link)).into_owned();
+
+        let mut outline = format!(
+            "    <outline text=\"{title}\" title=\"{title}\" xmlUrl=\"{}\"",
+            escape(&feed.feed_link)
+        );
+
+        if let Some(link) = &feed.link {
+            outline.push_str(&format!(" htmlUrl=\"{}\"", escape(link)));
+        }
+
+        if let Some(feed_kind) = &feed.feed_kind {
+            outline.push_str(&format!(" type=\"{}\"", escape(feed_kind.to_lowercase())));
+        }
+
+        outline.push_str("/>\n");
+
+        w.write_all(outline.as_bytes()).await?;
+    }
+
+    w.write_all(b"  </body>\n</opml>\n").await?;
+
+    w.flush().await?;
+
+    Ok(())
+}
+
+#[instrument(skip(state))]
+async fn feed_delete(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+) -> Result<impl IntoResponse, AppError> {
+    // its entries and tombstones go with it via `on delete cascade`,
+    // so a feed with starred entries is kept rather than lose them
+    let deleted = sqlx::query(
+        "
+        delete from feeds
+        where id = ?1
+        and not exists (
+            select 1
+            from entries
+            where feed_id = ?1
+            and starred_at is not null
+        )",
+    )
+    .bind(feed_id)
+    .execute(&state.pool)
+    .await?;
+
+    let mut headers = HeaderMap::new();
+
+    if deleted.rows_affected() == 0 {
+        let (starred,): (i64,) = sqlx::query_as(
+            "
+            select
+                count(*)
+            from entries
+            where feed_id = ?
+            and starred_at is not null",
+        )
+        .bind(feed_id)
+        .fetch_one(&state.pool)
+        .await?;
+
+        if starred > 0 {
+            let error_message =
+                format!("This feed has {starred} starred entries. Unstar them to delete the feed.");
+
+            headers.insert(
+                "HX-Trigger",
+                axum::Json(format!("{{\"feedDeleteError\":\"{error_message}\"}}"))
+                    .parse()
+                    .unwrap(),
+            );
+
+            return Ok((StatusCode::CONFLICT, headers, ""));
+        }
+    }
+
+    headers.insert("HX-Location", "/".parse().unwrap());
+    Ok((StatusCode::OK, headers, ""))
+}
+
+// TODO do similar error handling as adding feed
+async fn feed_refresh(
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool;
+    let http_client = state.http_client;
+
+    let counts = do_feed_refresh(&pool, &http_client, feed_id).await?;
+
+    Ok(flash(html! {
+        (counts)
+    }))
+}
+
+// get feed entries, conditional on the stored etag/last-modified
+// if not modified: update feed refreshed_at, done
+// get guids for challenger entries
+// get guids (or links, for old entries without a guid) for existing entries
+// set = remote_entries - existing_entries
+// in transaction:
+// - for entry in set: insert
+// - update feed refreshed_at
+// - update feed etag/last-modified
+#[instrument(skip(pool, http_client))]
+async fn do_feed_refresh(
+    pool: &sqlx::Pool<Sqlite>,
+    http_client: &reqwest::Client,
+    feed_id: i64,
+) -> anyhow::Result<FeedRefreshCounts> {
+    // queries before the fetch go straight to the pool,
+    // so a slow feed doesn't tie up a connection
+    let (feed_link, latest_etag, latest_last_modified): (String, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "
+    select
+        feed_link,
+        latest_etag,
+        latest_last_modified
+    from feeds
+    where id = ?",
+        )
+        .bind(feed_id)
+        .fetch_one(pool)
+        .await?;
+
+    let mut request = http_client.get(feed_link);
+
+    if let Some(etag) = latest_etag {
+        request = request.header(axum::http::header::IF_NONE_MATCH, etag);
+    }
+
+    if let Some(last_modified) = latest_last_modified {
+        request = request.header(axum::http::header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request.send().await?.error_for_status()?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        sqlx::query(
+            "
+            update feeds
+            set refreshed_at = ?
+            where id = ?",
+        )
+        .bind(chrono::Utc::now())
+        .bind(feed_id)
+        .execute(pool)
+        .await?;
+
+        return Ok(FeedRefreshCounts::default());
+    }
+
+    let (etag, last_modified) = caching_headers(response.headers());
+
+    let challenger_feed = response.bytes().await?;
+
+    let challenger_feed = parse_feed(&challenger_feed)?;
+
+    let mut conn = pool.acquire().await?;
+
+    // IMMEDIATE so that a background refresh and a user-initiated refresh
+    // of the same feed can't both decide the same entry is new
+    let mut tx = conn.begin_with("BEGIN IMMEDIATE").await?;
+
+    let existing_entries: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        "
+    select
+        guid,
+        link
+    from entries
+    where feed_id = ?",
+    )
+    .bind(feed_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let existing_entries_guids: HashSet<String> = existing_entries
+        .iter()
+        .filter_map(|(guid, _link)| guid.clone())
+        .collect();
+
+    // entries inserted before we stored guids can only be matched by link
+    let legacy_entries_links: HashSet<String> = existing_entries
+        .into_iter()
+        .filter_map(|(guid, link)| if guid.is_none() { link } else { None })
+        .collect();
+
+    let deleted_entries: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        "
+    select
+        guid,
+        link
+    from deleted_entries
+    where feed_id = ?",
+    )
+    .bind(feed_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let deleted_entries_guids: HashSet<String> = deleted_entries
+        .iter()
+        .filter_map(|(guid, _link)| guid.clone())
+        .collect();
+
+    let deleted_legacy_entries_links: HashSet<String> = deleted_entries
+        .into_iter()
+        .filter_map(|(guid, link)| if guid.is_none() { link } else { None })
+        .collect();
+
+    let mut counts = FeedRefreshCounts::default();
+
+    for entry in &challenger_feed.entries {
+        let Some(guid) = entry_guid(entry) else {
+            continue;
+        };
+
+        // the item is still in the remote feed, but we deleted it
+        if deleted_entries_guids.contains(guid)
+            || entry
+                .links
+                .first()
+                .is_some_and(|link| deleted_legacy_entries_links.contains(&link.href))
+        {
+            continue;
+        }
+
+        if existing_entries_guids.contains(guid) {
+            if update_entry(&mut tx, feed_id, guid, entry).await? {
+                counts.updated_entries += 1;
+            }
+
+            continue;
+        }
+
+        if let Some(link) = entry.links.first()
+            && legacy_entries_links.contains(&link.href)
+        {
+            let backfilled: Vec<(i64,)> = sqlx::query_as(
+                "
+                update entries
+                set
+                    guid = ?1,
+                    remote_updated_at = ?2,
+                    description = coalesce(description, ?3)
+                where feed_id = ?4
+                and link = ?5
+                and guid is null
+                returning id
+                ",
+            )
+            .bind(guid)
+            .bind(entry.updated)
+            .bind(entry.summary.as_ref().map(|summary| &summary.content))
+            .bind(feed_id)
+            .bind(&link.href)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for (entry_id,) in backfilled {
+                index_entry_for_search(&mut tx, entry_id).await?;
+            }
+
+            continue;
+        }
+
+        if insert_entry(&mut tx, feed_id, entry).await? {
+            counts.new_entries += 1;
+        }
+    }
+
+    sqlx::query(
+        "
+        update feeds
+        set
+            refreshed_at = ?1,
+            latest_etag = ?2,
+            latest_last_modified = ?3
+        where id = ?4",
+    )
+    .bind(chrono::Utc::now())
+    .bind(etag)
+    .bind(last_modified)
+    .bind(feed_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(counts)
+}
+
+#[derive(Debug, Default)]
+struct FeedRefreshCounts {
+    new_entries: u64,
+    updated_entries: u64,
+}
+
+impl std::fmt::Display for FeedRefreshCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "added {} new entries, updated {} entries",
+            self.new_entries, self.updated_entries
+        )
+    }
+}
+
+/// the `ETag` and `Last-Modified` values of a feed response,
+/// to be sent back as `If-None-Match` and `If-Modified-Since` on the next refresh
+fn caching_headers(headers: &HeaderMap) -> (Option<String>, Option<String>) {
+    let get = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+
+    (
+        get(axum::http::header::ETAG),
+        get(axum::http::header::LAST_MODIFIED),
+    )
+}
+
+async fn feeds_refresh(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool;
+    let http_client = state.http_client;
+    let refresh_concurrency = state.refresh_concurrency;
+
+    let results = refresh_all_feeds(&pool, &http_client, refresh_concurrency).await?;
+
+    Ok(flash(html! {
+        ul {
+            @for result in results {
+                li {
+                    (result.title)
+                    ": "
+                    @match result.outcome {
+                        Ok(counts) => {
+                            (counts)
+                        }
+                        Err(e) => {
+                            span class="text-error" {
+                                (format!("failed to refresh: {e}"))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// how long a single feed gets to refresh or import as part of a batch
+/// before it is given up on
+const FEED_REFRESH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+struct FeedRefreshResult {
+    feed_id: i64,
+    title: String,
+    outcome: anyhow::Result<FeedRefreshCounts>,
+}
+
+/// refresh every feed, with at most `concurrency` refreshes in flight at once.
+/// a feed that fails or times out does not stop the others,
+/// its error is reported in its result.
+#[instrument(skip(pool, http_client))]
+async fn refresh_all_feeds(
+    pool: &sqlx::Pool<Sqlite>,
+    http_client: &reqwest::Client,
+    concurrency: usize,
+) -> Result<Vec<FeedRefreshResult>, sqlx::Error> {
+    let feeds: Vec<(i64, String)> = sqlx::query_as(
+        "
+    select
+        id,
+        title
+    from feeds
+    order by title asc",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let http_client = http_client.clone();
+
+    let results = map_bounded(feeds, concurrency, move |(feed_id, title)| {
+        let pool = pool.clone();
+        let http_client = http_client.clone();
+
+        async move {
+            let outcome = tokio::time::timeout(
+                FEED_REFRESH_TIMEOUT,
+                do_feed_refresh(&pool, &http_client, feed_id),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out")));
+
+            FeedRefreshResult {
+                feed_id,
+                title,
+                outcome,
+            }
+        }
+    })
+    .await;
+
+    Ok(results)
+}
+
+/// run `f` on every item as its own task, with at most `concurrency` running at once.
+/// the outputs are in the same order as `items`.
+async fn map_bounded<T, F, Fut>(items: Vec<T>, concurrency: usize, f: F) -> Vec<Fut::Output>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (position, item) in items.into_iter().enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let fut = f(item);
+
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+
+            (position, fut.await)
+        });
+    }
+
+    let mut outputs = tasks.join_all().await;
+
+    outputs.sort_by_key(|(position, _)| *position);
+
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+/// refresh every feed every `interval`.
+/// a feed that fails to refresh or times out is logged and skipped,
+/// it will be tried again on the next tick.
+async fn refresh_feeds_periodically(
+    pool: sqlx::Pool<Sqlite>,
+    http_client: reqwest::Client,
+    interval: std::time::Duration,
+    concurrency: usize,
+) {
+    // the first refresh is one interval after startup, not at it,
+    // so restarts and socket activation don't refetch everything
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let results = match refresh_all_feeds(&pool, &http_client, concurrency).await {
+            Ok(results) => results,
+            Err(e) => {
+                tracing::error!("unable to load feeds for background refresh: {e}");
+                continue;
+            }
+        };
+
+        for FeedRefreshResult {
+            feed_id, outcome, ..
+        } in results
+        {
+            match outcome {
+                Ok(counts) => {
+                    tracing::info!(
+                        feed_id,
+                        new_entries = counts.new_entries,
+                        updated_entries = counts.updated_entries,
+                        "refreshed feed"
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(feed_id, "unable to refresh feed: {e:#}");
+                }
+            }
+        }
+    }
+}
+
+/// a message that fades out after a few seconds
+fn flash(msg: maud::Markup) -> maud::Markup {
+    html! {
+        div
+            class="fade-me-out"
+            // after 5 seconds, fire the request
+            hx-trigger="load delay:5s"
+            // allow the swap to take 3 seconds.
+            // this is the same amount of time as the transition.
+            hx-swap="delete swap:3s"
+            // empty response endpoint
+            hx-delete="/empty"
+        {
+            (msg)
+        }
+    }
+}
+
+async fn empty() -> impl IntoResponse {
+    ""
+}
+
+/// the cookie that holds the session token
+const SESSION_COOKIE: &str = "r2_session";
+
+/// how long a login lasts
+const SESSION_LIFETIME: chrono::TimeDelta = chrono::TimeDelta::days(30);
+
+fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    use argon2::PasswordHasher;
+    use argon2::password_hash::SaltString;
+    use argon2::password_hash::rand_core::OsRng;
+
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// checked against when the username doesn't exist,
+/// so that a login takes as long whether or not it does.
+/// it has the same parameters as `Argon2::default()`, which `hash_password` uses
+const DUMMY_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$A1PHikispuIM4qbruGOy3A$ngN+3g6wg8TrMIV91SxHNW8x7B9jNhtNBcUR3dID0rg";
+
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    use argon2::PasswordVerifier;
+
+    argon2::password_hash::PasswordHash::new(password_hash).is_ok_and(|password_hash| {
+        argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &password_hash)
+            .is_ok()
+    })
+}
+
+/// creates the user or changes their password,
+/// logging them out everywhere
+async fn set_password(
+    pool: &sqlx::Pool<Sqlite>,
+    username: &str,
+    password: &str,
+) -> anyhow::Result<()> {
+    let password_hash = hash_password(password).map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let mut tx = pool.begin().await?;
+
+    let (user_id,): (i64,) = sqlx::query_as(
+        "
+        insert into users (username, password_hash)
+        values (?1, ?2)
+        on conflict (username) do update
+        set
+            password_hash = excluded.password_hash,
+            updated_at = ?3
+        returning id
+        ",
+    )
+    .bind(username)
+    .bind(password_hash)
+    .bind(chrono::Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "
+        delete from sessions
+        where user_id = ?",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// only a hash of each token is stored,
+/// so that reading the database doesn't hand out live sessions
+fn hash_session_token(token: &str) -> String {
+    use sha2::Digest;
+
+    sha2::Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// starts a session for the user, returning its token
+async fn create_session(pool: &sqlx::Pool<Sqlite>, user_id: i64) -> Result<String, sqlx::Error> {
+    use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let now = chrono::Utc::now();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "
+        delete from sessions
+        where julianday(expires_at) <= julianday(?)",
+    )
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "
+        insert into sessions (token_hash, user_id, expires_at)
+        values (?1, ?2, ?3)",
+    )
+    .bind(hash_session_token(&token))
+    .bind(user_id)
+    .bind(now + SESSION_LIFETIME)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(token)
+}
+
+/// the session token from the request's cookies, if there is one
+fn session_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token)
+}
+
+fn session_cookie(token: &str, max_age: chrono::TimeDelta, secure: bool) -> String {
+    format!(
+        "{SESSION_COOKIE}={token}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        max_age.num_seconds(),
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// lets requests with a live session through,
+/// and sends everyone else to the login page
+async fn require_login(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<Response, AppError> {
+    if let Some(token) = session_token(request.headers()) {
+        let session: Option<(i64,)> = sqlx::query_as(
+            "
+            select
+                user_id
+            from sessions
+            where token_hash = ?1
+            and julianday(expires_at) > julianday(?2)",
+        )
+        .bind(hash_session_token(token))
+        .bind(chrono::Utc::now())
+        .fetch_optional(&state.pool)
+        .await?;
+
+        if session.is_some() {
+            return Ok(next.run(request).await);
+        }
+    }
+
+    // htmx would swap a plain redirect into the page, so tell it to navigate instead
+    if request.headers().contains_key("HX-Request") {
+        let mut headers = HeaderMap::new();
+        headers.insert("HX-Redirect", "/login".parse().unwrap());
+        return Ok((StatusCode::UNAUTHORIZED, headers).into_response());
+    }
+
+    Ok(axum::response::Redirect::to("/login").into_response())
+}
+
+#[derive(Deserialize)]
+struct LoginParams {
+    username: String,
+    password: String,
+}
+
+fn login_page(error: Option<&str>, no_users: bool) -> maud::Markup {
+    layout! {
+        html! {
+            div class="grid justify-items-center p-4" {
+                form class="flex flex-col gap-2 w-full max-w-xs" method="post" action="/login" {
+                    h1 class="text-lg" {
+                        "Log in"
+                    }
+                    @if no_users {
+                        div role="alert" class="alert" {
+                            "There are no users yet. Create one with "
+                            code { "r2 set-password <username>" }
+                        }
+                    }
+                    @if let Some(error) = error {
+                        div role="alert" class="alert alert-error" {
+                            (error)
+                        }
+                    }
+                    input
+                        class="input"
+                        type="text"
+                        name="username"
+                        placeholder="Username"
+                        autocomplete="username"
+                        required
+                        autofocus;
+                    input
+                        class="input"
+                        type="password"
+                        name="password"
+                        placeholder="Password"
+                        autocomplete="current-password"
+                        required;
+                    button class="btn" type="submit" {
+                        "Log in"
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn any_users(pool: &sqlx::Pool<Sqlite>) -> Result<bool, sqlx::Error> {
+    let (any_users,): (bool,) = sqlx::query_as("select exists (select 1 from users)")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(any_users)
+}
+
+#[instrument(skip(state))]
+async fn login_show(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    Ok(login_page(None, !any_users(&state.pool).await?))
+}
+
+#[instrument(skip(state, params))]
+async fn login(
+    State(state): State<AppState>,
+    axum::Form(params): axum::Form<LoginParams>,
+) -> Result<Response, AppError> {
+    let user: Option<(i64, String)> = sqlx::query_as(
+        "
+        select
+            id,
+            password_hash
+        from users
+        where username = ?",
+    )
+    .bind(&params.username)
+    .fetch_optional(&state.pool)
+    .await?;
+
+    // argon2 is deliberately slow, so keep it off the async threads
+    let user_id = tokio::task::spawn_blocking(move || match user {
+        Some((id, password_hash)) => {
+            verify_password(&params.password, &password_hash).then_some(id)
+        }
+        None => {
+            verify_password(&params.password, DUMMY_PASSWORD_HASH);
+            None
+        }
+    })
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            login_page(
+                Some("Wrong username or password"),
+                !any_users(&state.pool).await?,
+            ),
+        )
+            .into_response());
+    };
+
+    let token = create_session(&state.pool, user_id).await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::SET_COOKIE,
+        session_cookie(&token, SESSION_LIFETIME, state.secure_cookies)
+            .parse()
+            .unwrap(),
+    );
+
+    Ok((headers, axum::response::Redirect::to("/")).into_response())
+}
+
+#[instrument(skip(state, headers))]
+async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(token) = session_token(&headers) {
+        sqlx::query(
+            "
+            delete from sessions
+            where token_hash = ?",
+        )
+        .bind(hash_session_token(token))
+        .execute(&state.pool)
+        .await?;
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::SET_COOKIE,
+        session_cookie("", chrono::TimeDelta::zero(), state.secure_cookies)
+            .parse()
+            .unwrap(),
+    );
+    headers.insert("HX-Location", "/login".parse().unwrap());
+
+    Ok((headers, ""))
+}
+
+async fn static_handler(uri: Uri, headers: HeaderMap) -> impl IntoResponse {
+    let mut path = uri.path().trim_start_matches('/').to_string();
+
+    if path.starts_with("dist/") {
+        path = path.replace("dist/", "");
+    }
+
+    StaticFile(path, headers)
+}
+
+#[instrument(skip(conn))]
+async fn initialize_db(conn: &mut sqlx::SqliteConnection) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let (schema_version,): (u64,) = sqlx::query_as("PRAGMA user_version")
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or((0,));
+
+    if schema_version == 0 {
+        tx.execute("PRAGMA user_version=1").await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS feeds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT,
+        feed_link TEXT,
+        link TEXT,
+        feed_kind TEXT,
+        refreshed_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER,
+        title TEXT,
+        author TEXT,
+        pub_date TIMESTAMP,
+        description TEXT,
+        content TEXT,
+        link TEXT,
+        read_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index
+        ON entries (feed_id, pub_date, inserted_at)",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 1 {
+        tx.execute("PRAGMA user_version=2").await?;
+
+        sqlx::query("ALTER TABLE feeds ADD COLUMN latest_etag TEXT")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if schema_version <= 2 {
+        tx.execute("PRAGMA user_version=3").await?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS feeds_feed_link ON feeds (feed_link)")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if schema_version <= 3 {
+        tx.execute("PRAGMA user_version=4").await?;
+
+        sqlx::query("ALTER TABLE feeds ADD COLUMN latest_last_modified TEXT")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if schema_version <= 4 {
+        tx.execute("PRAGMA user_version=5").await?;
+
+        sqlx::query("ALTER TABLE entries ADD COLUMN guid TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS entries_feed_id_and_guid_index
+        ON entries (feed_id, guid)",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 5 {
+        tx.execute("PRAGMA user_version=6").await?;
+
+        sqlx::query("ALTER TABLE entries ADD COLUMN remote_updated_at TIMESTAMP")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if schema_version <= 6 {
+        tx.execute("PRAGMA user_version=7").await?;
+
+        // summaries were never stored before this version,
+        // so make the next refresh of every feed a full fetch
+        // rather than a 304, so existing entries get them backfilled
+        sqlx::query(
+            "UPDATE feeds
+        SET latest_etag = NULL, latest_last_modified = NULL",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 7 {
+        tx.execute("PRAGMA user_version=8").await?;
+
+        // rowid is entries.id
+        sqlx::query(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS entries_search USING fts5 (
+        title,
+        author,
+        content
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE TRIGGER IF NOT EXISTS entries_search_delete
+        AFTER DELETE ON entries
+        BEGIN
+            DELETE FROM entries_search WHERE rowid = old.id;
+        END",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let entry_ids: Vec<(i64,)> = sqlx::query_as("SELECT id FROM entries")
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for (entry_id,) in entry_ids {
+            index_entry_for_search(&mut tx, entry_id).await?;
+        }
+    }
+
+    if schema_version <= 8 {
+        tx.execute("PRAGMA user_version=9").await?;
+
+        // sqlite can't add a foreign key to an existing column,
+        // so rebuild entries with one
+
+        sqlx::query(
+            "DELETE FROM entries
+        WHERE feed_id IS NULL
+        OR feed_id NOT IN (SELECT id FROM feeds)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE entries_new (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+        title TEXT,
+        author TEXT,
+        pub_date TIMESTAMP,
+        description TEXT,
+        content TEXT,
+        link TEXT,
+        read_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        guid TEXT,
+        remote_updated_at TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO entries_new (
+            id, feed_id, title, author, pub_date, description, content, link,
+            read_at, inserted_at, updated_at, guid, remote_updated_at
+        )
+        SELECT
+            id, feed_id, title, author, pub_date, description, content, link,
+            read_at, inserted_at, updated_at, guid, remote_updated_at
+        FROM entries",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE entries").execute(&mut *tx).await?;
+
+        sqlx::query("ALTER TABLE entries_new RENAME TO entries")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index
+        ON entries (feed_id, pub_date, inserted_at)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS entries_feed_id_and_guid_index
+        ON entries (feed_id, guid)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE TRIGGER IF NOT EXISTS entries_search_delete
+        AFTER DELETE ON entries
+        BEGIN
+            DELETE FROM entries_search WHERE rowid = old.id;
+        END",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 9 {
+        tx.execute("PRAGMA user_version=10").await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS deleted_entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+        guid TEXT,
+        link TEXT,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS deleted_entries_feed_id_and_guid_index
+        ON deleted_entries (feed_id, guid)",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 10 {
+        tx.execute("PRAGMA user_version=11").await?;
+
+        sqlx::query("ALTER TABLE entries ADD COLUMN starred_at TIMESTAMP")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if schema_version <= 11 {
+        tx.execute("PRAGMA user_version=12").await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS folders (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS folders_title ON folders (title)")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "ALTER TABLE feeds ADD COLUMN folder_id INTEGER
+        REFERENCES folders(id) ON DELETE SET NULL",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if schema_version <= 12 {
+        tx.execute("PRAGMA user_version=13").await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS users (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        username TEXT NOT NULL,
+        password_hash TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS users_username ON users (username)")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        token_hash TEXT NOT NULL,
+        user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        expires_at TIMESTAMP NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS sessions_token_hash ON sessions (token_hash)",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+struct AppError(anyhow::Error);
+
+// Tell axum how to convert `AppError` into a response.
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Something went wrong: {}", self.0),
+        )
+            .into_response()
+    }
+}
+
+impl<E> From<E> for AppError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        Self(err.into())
+    }
+}
+
+#[derive(Embed)]
+#[folder = "dist/"]
+struct Asset;
+
+/// an embedded file and the headers of the request for it
+pub struct StaticFile<T>(pub T, pub HeaderMap);
+
+/// assets are linked with their hash in the query string (see `asset_href`),
+/// so a new build changes the url and it's fine to cache them for good
+const ASSET_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+impl<T> IntoResponse for StaticFile<T>
+where
+    T: Into<String>,
+{
+    fn into_response(self) -> Response {
+        let path = self.0.into();
+
+        match Asset::get(path.as_str()) {
+            Some(content) => {
+                let etag = format!("\"{}\"", asset_hash(&content));
+
+                let unchanged = self
+                    .1
+                    .get(axum::http::header::IF_NONE_MATCH)
+                    .and_then(|if_none_match| if_none_match.to_str().ok())
+                    .is_some_and(|if_none_match| {
+                        if_none_match
+                            .split(',')
+                            .map(|tag| tag.trim())
+                            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+                    });
+
+                if unchanged {
+                    return (
+                        StatusCode::NOT_MODIFIED,
+                        [
+                            (axum::http::header::ETAG, etag.as_str()),
+                            (axum::http::header::CACHE_CONTROL, ASSET_CACHE_CONTROL),
+                        ],
+                    )
+                        .into_response();
+                }
+
+                let mime = mime_guess::from_path(path).first_or_octet_stream();
+                (
+                    [
+                        (axum::http::header::CONTENT_TYPE, mime.as_ref()),
+                        (axum::http::header::ETAG, etag.as_str()),
+                        (axum::http::header::CACHE_CONTROL, ASSET_CACHE_CONTROL),
+                    ],
+                    content.data,
+                )
+                    .into_response()
+            }
+            None => (StatusCode::NOT_FOUND, "404 Not Found").into_response(),
+        }
+    }
+}
+
+/// the hex sha256 of an embedded file, as computed by rust-embed
+fn asset_hash(file: &rust_embed::EmbeddedFile) -> String {
+    file.metadata
+        .sha256_hash()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// the url for an embedded file, versioned by its hash
+fn asset_href(path: &str) -> String {
+    match Asset::get(path) {
+        Some(file) => format!("/dist/{path}?v={}", &asset_hash(&file)[..16]),
+        None => format!("/dist/{path}"),
+    }
+}
+
+/// everything in here is cheap to clone and safe to share,
+/// so handlers get their own copy rather than locking anything
+#[derive(Clone, Debug)]
+struct AppState {
+    pool: sqlx::Pool<Sqlite>,
+    http_client: reqwest::Client,
+    refresh_concurrency: usize,
+    mark_read: MarkRead,
+    /// whether the session cookie should only be sent over HTTPS
+    secure_cookies: bool,
+}
+
+/// when to mark an entry read without clicking "Mark read"
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum MarkRead {
+    /// only mark entries read by hand
+    Off,
+    /// mark an entry read as soon as it's opened
+    Open,
+    /// mark an entry read once it's been scrolled to the end
+    Scroll,
+}
+
+#[derive(Debug, Parser)]
+struct Config {
+    #[arg(long, env, default_value = "feeds.db")]
+    database: String,
+    /// the port to listen on localhost, if --listen isn't given
+    #[arg(long, env, default_value = "3000")]
+    port: u16,
+    /// where to listen: `host:port`, `[::1]:port`, or `unix:/path/to/socket`.
+    /// a socket passed in by systemd socket activation takes precedence
+    #[arg(long, env)]
+    listen: Option<ListenAddress>,
+    /// serve HTTPS with this PEM certificate chain,
+    /// which is reloaded when it changes on disk
+    #[arg(long, env, requires = "tls_key")]
+    tls_cert: Option<std::path::PathBuf>,
+    /// the PEM private key for --tls-cert
+    #[arg(long, env, requires = "tls_cert")]
+    tls_key: Option<std::path::PathBuf>,
+    /// also listen for plain HTTP here, and redirect it to HTTPS
+    #[arg(long, env, requires = "tls_cert")]
+    redirect_http: Option<ListenAddress>,
+    /// how often to refresh every feed in the background, in seconds.
+    /// 0 disables background refresh.
+    #[arg(long, env, default_value = "3600")]
+    refresh_interval: u64,
+    /// how many feeds to refresh at once when refreshing all feeds
+    #[arg(long, env, default_value = "4")]
+    refresh_concurrency: usize,
+    /// when to automatically mark an entry read
+    #[arg(long, env, value_enum, default_value = "off")]
+    mark_read: MarkRead,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Debug)]
+enum ListenAddress {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl FromStr for ListenAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            #[cfg(unix)]
+            return Ok(ListenAddress::Unix(path.into()));
+
+            #[cfg(not(unix))]
+            return Err(format!(
+                "unix sockets aren't supported on this platform, got {path}"
+            ));
+        }
+
+        // the host is resolved when binding, this only checks that there's a port
+        match s.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                Ok(ListenAddress::Tcp(s.to_string()))
+            }
+            _ => Err(format!(
+                "expected host:port, [ipv6]:port, or unix:/path/to/socket, got {s}"
+            )),
+        }
+    }
+}
+
+enum Listener {
+    Tcp(tokio::net::TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    /// the socket systemd passed in, if r2 was started by socket activation
+    fn from_systemd() -> anyhow::Result<Option<Self>> {
+        let mut listenfd = listenfd::ListenFd::from_env();
+
+        // a socket of the wrong kind is left in place, so try the other kind
+        let listener = match listenfd.take_tcp_listener(0) {
+            Ok(Some(listener)) => {
+                listener.set_nonblocking(true)?;
+                Some(Listener::Tcp(tokio::net::TcpListener::from_std(listener)?))
+            }
+            Ok(None) => None,
+            #[cfg(not(unix))]
+            Err(e) => return Err(e.into()),
+            #[cfg(unix)]
+            Err(_) => match listenfd.take_unix_listener(0)? {
+                Some(listener) => {
+                    listener.set_nonblocking(true)?;
+                    Some(Listener::Unix(tokio::net::UnixListener::from_std(
+                        listener,
+                    )?))
+                }
+                None => None,
+            },
+        };
+
+        Ok(listener)
+    }
+
+    async fn bind(address: &ListenAddress) -> anyhow::Result<Self> {
+        match address {
+            ListenAddress::Tcp(address) => {
+                Ok(Listener::Tcp(tokio::net::TcpListener::bind(address).await?))
+            }
+            #[cfg(unix)]
+            ListenAddress::Unix(path) => {
+                // a socket left over from a previous run would make bind fail.
+                // one that still accepts connections belongs to a running instance,
+                // so that is left alone and bind fails instead
+                if std::fs::symlink_metadata(path)
+                    .is_ok_and(|metadata| metadata.file_type().is_socket())
+                    && std::os::unix::net::UnixStream::connect(path).is_err()
+                {
+                    std::fs::remove_file(path)?;
+                }
+
+                Ok(Listener::Unix(tokio::net::UnixListener::bind(path)?))
+            }
+        }
+    }
+}
+
+/// serves `router` on `listener`, over TLS if there's an acceptor
+async fn serve(
+    listener: Listener,
+    router: Router,
+    tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
+) -> std::io::Result<()> {
+    match (listener, tls_acceptor) {
+        (Listener::Tcp(listener), None) => {
+            tracing::info!("listening on http://{}", listener.local_addr()?);
+            axum::serve(listener, router).await
+        }
+        (Listener::Tcp(listener), Some(tls_acceptor)) => {
+            tracing::info!("listening on https://{}", listener.local_addr()?);
+            axum::serve(TlsListener::new(listener, tls_acceptor)?, router).await
+        }
+        #[cfg(unix)]
+        (Listener::Unix(listener), None) => {
+            tracing::info!("listening on {:?}", listener.local_addr()?);
+            axum::serve(listener, router).await
+        }
+        #[cfg(unix)]
+        (Listener::Unix(listener), Some(tls_acceptor)) => {
+            tracing::info!("listening with TLS on {:?}", listener.local_addr()?);
+            axum::serve(TlsListener::new(listener, tls_acceptor)?, router).await
+        }
+    }
+}
+
+/// how long a client gets to finish a TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// how often to check whether the certificate files have changed
+const TLS_RELOAD_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// wraps a listener's connections in TLS.
+/// handshakes happen in their own tasks,
+/// so a slow client can't hold up everyone else's connections
+struct TlsListener<L: axum::serve::Listener> {
+    connections: tokio::sync::mpsc::Receiver<(tokio_rustls::server::TlsStream<L::Io>, L::Addr)>,
+    local_addr: L::Addr,
+}
+
+impl<L> TlsListener<L>
+where
+    L: axum::serve::Listener,
+    L::Addr: Clone,
+{
+    fn new(mut listener: L, tls_acceptor: tokio_rustls::TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+
+        let (sender, connections) = tokio::sync::mpsc::channel(64);
+
+        tokio::spawn(async move {
+            // stops once the TlsListener is dropped
+            while !sender.is_closed() {
+                let (io, addr) = listener.accept().await;
+                let tls_acceptor = tls_acceptor.clone();
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls_acceptor.accept(io)).await
+                    {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!("TLS handshake failed: {e}"),
+                        Err(_) => tracing::debug!("TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            connections,
+            local_addr,
+        })
+    }
+}
+
+impl<L> axum::serve::Listener for TlsListener<L>
+where
+    L: axum::serve::Listener,
+    L::Addr: Clone,
+{
+    type Io = tokio_rustls::server::TlsStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        self.connections
+            .recv()
+            .await
+            .expect("the TLS accept loop runs as long as the listener exists")
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr.clone())
+    }
+}
+
+fn tls_acceptor(certificate: Arc<ReloadingCertificate>) -> tokio_rustls::TlsAcceptor {
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("the ring provider supports the default protocol versions")
+    .with_no_client_auth()
+    .with_cert_resolver(certificate);
+
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    tokio_rustls::TlsAcceptor::from(Arc::new(config))
+}
+
+/// the server's certificate and key,
+/// reloaded when either file changes so renewing a certificate doesn't need a restart
+#[derive(Debug)]
+struct ReloadingCertificate {
+    cert_path: std::path::PathBuf,
+    key_path: std::path::PathBuf,
+    current: std::sync::RwLock<Arc<rustls::sign::CertifiedKey>>,
+}
+
+impl ReloadingCertificate {
+    fn load(cert_path: std::path::PathBuf, key_path: std::path::PathBuf) -> anyhow::Result<Self> {
+        let current = load_certified_key(&cert_path, &key_path)?;
+
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: std::sync::RwLock::new(Arc::new(current)),
+        })
+    }
+
+    /// keeps serving the old certificate if the new one can't be loaded
+    fn reload(&self) -> anyhow::Result<()> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+
+        *self.current.write().unwrap() = Arc::new(certified_key);
+
+        Ok(())
+    }
+
+    fn modified(&self) -> [Option<std::time::SystemTime>; 2] {
+        [&self.cert_path, &self.key_path]
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+    }
+
+    /// polls rather than watching for events, which also catches
+    /// certificates that are renewed by swapping a symlink
+    async fn watch(self: Arc<Self>, interval: std::time::Duration) {
+        let mut modified = self.modified();
+
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            let now_modified = self.modified();
+
+            if now_modified == modified {
+                continue;
+            }
 
-                div class="divider" {}
+            modified = now_modified;
 
-                div {
-                    a class="link p-2" href=(format!("/feeds/{}", entry.feed_id)) {
-                        "Back"
-                    }
-                    @if entry.read_at.is_none() {
-                        a
-                            class="link p-2"
-                            hx-put=(format!("/entries/{}?action=toggle_read_unread", entry_id))
-                            hx-swap="innerHTML"
-                        {
-                            "Mark read"
-                        }
-                    } @else {
-                        a
-                            class="link p-2"
-                            hx-put=(format!("/entries/{}?action=toggle_read_unread", entry_id))
-                            hx-swap="innerHTML"
-                        {
-                            "Mark unread"
-                        }
-                    }
-                    a
-                        class="link p-2"
-                        href=(entry.link)
-                        target="_blank"
-                    {
-                        "View original"
-                    }
+            match self.reload() {
+                Ok(()) => {
+                    tracing::info!("reloaded TLS certificate from {}", self.cert_path.display())
                 }
+                // the cert and key may have been caught halfway through being replaced,
+                // in which case the next change will get picked up
+                Err(e) => tracing::warn!("could not reload TLS certificate: {e:#}"),
             }
         }
-    })
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct EntryUpdateParams {
-    action: EntryUpdateAction,
+impl rustls::server::ResolvesServerCert for ReloadingCertificate {
+    fn resolve(
+        &self,
+        _client_hello: rustls::server::ClientHello<'_>,
+    ) -> Option<Arc<rustls::sign::CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
 }
 
-#[derive(Deserialize, Debug)]
-enum EntryUpdateAction {
-    #[serde(rename = "refresh")]
-    Refresh,
-    #[serde(rename = "toggle_read_unread")]
-    ToggleReadUnread,
-}
+fn load_certified_key(
+    cert_path: &std::path::Path,
+    key_path: &std::path::Path,
+) -> anyhow::Result<rustls::sign::CertifiedKey> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 
-#[instrument(skip(state))]
-async fn entry_update(
-    State(state): State<Arc<Mutex<AppState>>>,
-    Path(entry_id): Path<i64>,
-    Query(params): Query<EntryUpdateParams>,
-) -> Result<impl IntoResponse, AppError> {
-    match params.action {
-        EntryUpdateAction::Refresh => {
-            let state = state.lock().await;
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("could not read {}: {e}", cert_path.display()))?;
 
-            let mut conn = state.pool.acquire().await?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| anyhow::anyhow!("could not read {}: {e}", key_path.display()))?;
 
-            let (_read_at,): (String,) = sqlx::query_as(
-                "
-                update entries
-                set read_at = ?1
-                where entry_id = ?2
-                returning read_at
-                ",
-            )
-            .bind(chrono::Utc::now())
-            .bind(entry_id)
-            .fetch_one(&mut *conn)
-            .await?;
+    Ok(rustls::sign::CertifiedKey::from_der(
+        certs,
+        key,
+        &rustls::crypto::ring::default_provider(),
+    )?)
+}
 
-            Ok(html! {
-                "ok"
-            })
+/// sends plain HTTP requests to the same place over HTTPS
+async fn redirect_to_https(
+    State(https_port): State<u16>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Response {
+    let Some(host) = headers
+        .get(axum::http::header::HOST)
+        .and_then(|host| host.to_str().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "missing Host header").into_response();
+    };
+
+    // drop the plain HTTP port, taking care not to mangle an IPv6 address
+    let host = match host.rsplit_once(':') {
+        Some((host, port))
+            if port.parse::<u16>().is_ok() && (host.ends_with(']') || !host.contains(':')) =>
+        {
+            host
         }
-        EntryUpdateAction::ToggleReadUnread => {
-            let state = state.lock().await;
+        _ => host,
+    };
 
-            let mut conn = state.pool.acquire().await?;
+    let authority = if https_port == 443 {
+        host.to_string()
+    } else {
+        format!("{host}:{https_port}")
+    };
 
-            let mut tx = conn.begin_with("BEGIN IMMEDIATE").await?;
+    let path_and_query = uri
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
 
-            let (read_at,): (Option<String>,) = sqlx::query_as(
-                "
-            select
-                read_at
-            from entries
-            where id = ?
-            ",
-            )
-            .bind(entry_id)
-            .fetch_one(&mut *tx)
-            .await?;
+    axum::response::Redirect::permanent(&format!("https://{authority}{path_and_query}"))
+        .into_response()
+}
 
-            let out = if read_at.is_some() {
-                sqlx::query(
-                    "
-                update entries
-                set read_at = null
-                where id = ?
-                ",
-                )
-                .bind(entry_id)
-                .execute(&mut *tx)
-                .await?;
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// write every feed to an OPML file and exit
+    ExportOpml {
+        /// where to write the OPML file
+        path: std::path::PathBuf,
+    },
+    /// create a user, or reset their password, and exit.
+    /// the password is prompted for, or read from stdin if it isn't a terminal
+    SetPassword { username: String },
+}
+
+fn router(state: AppState) -> Router {
+    let protected = Router::new()
+        .route("/", get(feed_index))
+        .route("/feeds", post(feed_create))
+        .route("/feeds/refresh", put(feeds_refresh))
+        .route("/feeds/import", get(feeds_import_show).post(feeds_import))
+        .route("/feeds/export", get(feeds_export))
+        .route("/feeds/{feed_id}", get(feed_show).delete(feed_delete))
+        .route("/feeds/{feed_id}/refresh", put(feed_refresh))
+        .route("/feeds/{feed_id}/entries", post(entries_update))
+        .route("/feeds/{feed_id}/mark_read", post(feed_mark_read))
+        .route("/feeds/mark_read", post(feeds_mark_read))
+        .route("/feeds/{feed_id}/folder", put(feed_folder_update))
+        .route("/folders", post(folder_create))
+        .route(
+            "/folders/{folder_id}",
+            get(folder_show).delete(folder_delete),
+        )
+        .route(
+            "/entries/{entry_id}",
+            get(entry_show).put(entry_update).delete(entry_delete),
+        )
+        .route("/search", get(search))
+        .route("/entries", get(entries_index))
+        .route("/starred", get(starred_index))
+        .route("/empty", delete(empty))
+        .route("/logout", post(logout))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            require_login,
+        ));
+
+    Router::new()
+        .merge(protected)
+        .route("/login", get(login_show).post(login))
+        .route("/dist/{*file}", get(static_handler))
+        .with_state(state)
+        .layer(tower_http::compression::CompressionLayer::new())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let config = Config::parse();
+
+    let opts =
+        sqlx::sqlite::SqliteConnectOptions::from_str(&format!("sqlite://{}", config.database))?
+            .busy_timeout(std::time::Duration::from_secs(5))
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .create_if_missing(true)
+            // deleting feeds, folders and users relies on the `on delete` actions
+            .foreign_keys(true);
+
+    let pool = sqlx::SqlitePool::connect_with(opts).await?;
 
-                html! {
-                    "Mark read"
+    initialize_db(&mut *pool.acquire().await?).await?;
+
+    if let Some(command) = config.command {
+        match command {
+            Command::ExportOpml { path } => {
+                // write next to the target and rename it into place,
+                // so a failed export never replaces the last good one
+                let mut tmp_path = path.clone().into_os_string();
+                tmp_path.push(".tmp");
+                let tmp_path = std::path::PathBuf::from(tmp_path);
+
+                let mut file = tokio::io::BufWriter::new(tokio::fs::File::create(&tmp_path).await?);
+
+                if let Err(e) = write_opml(&pool, &mut file).await {
+                    drop(file);
+                    let _ = tokio::fs::remove_file(&tmp_path).await;
+                    return Err(e);
                 }
-            } else {
-                sqlx::query(
-                    "
-                update entries
-                set read_at = ?
-                where id = ?
-                ",
-                )
-                .bind(chrono::Utc::now())
-                .bind(entry_id)
-                .execute(&mut *tx)
-                .await?;
 
-                html! {
-                    "Mark unread"
+                file.into_inner().sync_all().await?;
+
+                tokio::fs::rename(&tmp_path, &path).await?;
+            }
+            Command::SetPassword { username } => {
+                let password = if std::io::stdin().is_terminal() {
+                    rpassword::prompt_password(format!("Password for {username}: "))?
+                } else {
+                    let mut password = String::new();
+                    std::io::stdin().read_line(&mut password)?;
+                    password.trim_end_matches(['\r', '\n']).to_string()
+                };
+
+                if password.is_empty() {
+                    anyhow::bail!("the password can't be empty");
                 }
-            };
 
-            tx.commit().await?;
+                set_password(&pool, &username, &password).await?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    let http_client = reqwest::Client::new();
+
+    if config.refresh_interval > 0 {
+        tokio::spawn(refresh_feeds_periodically(
+            pool.clone(),
+            http_client.clone(),
+            std::time::Duration::from_secs(config.refresh_interval),
+            config.refresh_concurrency,
+        ));
+    }
+
+    let state = AppState {
+        pool,
+        http_client,
+        refresh_concurrency: config.refresh_concurrency,
+        mark_read: config.mark_read,
+        secure_cookies: config.tls_cert.is_some(),
+    };
+
+    let router = router(state);
+
+    #[cfg(debug_assertions)]
+    let router = router.layer(tower_livereload::LiveReloadLayer::new());
+
+    let listener = match Listener::from_systemd()? {
+        Some(listener) => listener,
+        None => {
+            let address = config
+                .listen
+                .unwrap_or_else(|| ListenAddress::Tcp(format!("localhost:{}", config.port)));
+
+            Listener::bind(&address).await?
+        }
+    };
 
-            Ok(out)
+    let tls_acceptor = match (config.tls_cert, config.tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let certificate = Arc::new(ReloadingCertificate::load(cert_path, key_path)?);
+            tokio::spawn(certificate.clone().watch(TLS_RELOAD_INTERVAL));
+            Some(tls_acceptor(certificate))
         }
+        _ => None,
+    };
+
+    if let Some(address) = config.redirect_http {
+        let https_port = match &listener {
+            Listener::Tcp(listener) => listener.local_addr()?.port(),
+            #[cfg(unix)]
+            Listener::Unix(_) => 443,
+        };
+
+        let redirect_listener = Listener::bind(&address).await?;
+
+        let redirect_router = Router::new()
+            .fallback(redirect_to_https)
+            .with_state(https_port);
+
+        tokio::spawn(async move {
+            if let Err(e) = serve(redirect_listener, redirect_router, None).await {
+                tracing::error!("HTTP redirect listener stopped: {e}");
+            }
+        });
     }
-}
 
-#[derive(Debug, Error)]
-enum FeedCreateError {
-    #[error("bad input")]
-    BadInput(&'static str),
-    #[error("network error")]
-    NetworkError(#[from] reqwest::Error),
-    #[error("feed parse error")]
-    FeedParseError(#[from] feed_rs::parser::ParseFeedError),
-    #[error("database error")]
-    DatabaseError(#[from] sqlx::Error),
+    serve(listener, router, tls_acceptor).await?;
+
+    Ok(())
 }
 
-async fn do_feed_create(
-    headers: HeaderMap,
-    State(state): State<Arc<Mutex<AppState>>>,
-) -> Result<(), FeedCreateError> {
-    let feed = headers.get("HX-Prompt").ok_or(FeedCreateError::BadInput(
-        "somehow the HX-Prompt header did not get included",
-    ))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let s = feed
-        .to_str()
-        .map_err(|_| FeedCreateError::BadInput("could not convert HX-Prompt value to str"))?;
+    /// state backed by a fresh database in the temp dir, and that database's path
+    async fn test_state(name: &str) -> (AppState, std::path::PathBuf) {
+        let database =
+            std::env::temp_dir().join(format!("r2-test-{name}-{}.db", std::process::id()));
 
-    let feed_url =
-        Url::parse(s).map_err(|_| FeedCreateError::BadInput("could not parse str as URL"))?;
+        let opts = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(&database)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .create_if_missing(true)
+            .foreign_keys(true);
 
-    let state = state.lock().await;
-    let mut conn = state.pool.acquire().await?;
-    let http_client = state.http_client.clone();
-    drop(state);
+        let pool = sqlx::SqlitePool::connect_with(opts).await.unwrap();
 
-    let already_exists: Option<(bool,)> = sqlx::query_as(
-        "
-    select
-        1
-    from feeds
-    where feed_link = ?",
-    )
-    .bind(feed_url.as_str())
-    .fetch_optional(&mut *conn)
-    .await?;
+        initialize_db(&mut pool.acquire().await.unwrap())
+            .await
+            .unwrap();
 
-    if already_exists.is_some() {
-        return Err(FeedCreateError::BadInput("Feed already exists"));
+        let state = AppState {
+            pool,
+            http_client: reqwest::Client::new(),
+            refresh_concurrency: 1,
+            mark_read: MarkRead::Off,
+            secure_cookies: false,
+        };
+
+        (state, database)
     }
 
-    let response = http_client
-        .get(feed_url.clone())
-        .send()
-        .await?
-        .error_for_status()?;
+    async fn remove_test_database(pool: sqlx::Pool<Sqlite>, database: &std::path::Path) {
+        pool.close().await;
 
-    let body = response.bytes().await?;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", database.display()));
+        }
+    }
 
-    let feed = feed_rs::parser::parse(&*body)?;
+    /// a feed that never answers shouldn't hold up anyone else's page loads
+    #[tokio::test]
+    async fn pages_load_while_a_refresh_is_stalled() {
+        // accepts connections and then never responds on them
+        let slow_server = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let slow_addr = slow_server.local_addr().unwrap();
+        let (connected_tx, mut connected_rx) = tokio::sync::mpsc::channel(1);
 
-    let mut tx = conn.begin().await?;
+        tokio::spawn(async move {
+            let mut stalled = vec![];
+            loop {
+                let (socket, _) = slow_server.accept().await.unwrap();
+                stalled.push(socket);
+                let _ = connected_tx.send(()).await;
+            }
+        });
 
-    #[derive(FromRow)]
-    struct Feed {
-        id: i64,
-    }
+        let (state, database) = test_state("stalled").await;
+        let pool = state.pool.clone();
 
-    let Feed { id: feed_id } = sqlx::query_as(
-        "
-        insert into feeds (title, link, feed_link, feed_kind)
-        values (?1, ?2, ?3, ?4)
-        returning id",
-    )
-    .bind(&feed.title.as_ref().unwrap().content)
-    .bind(&feed.links.first().unwrap().href)
-    .bind(feed_url.as_str())
-    .bind(match feed.feed_type {
-        feed_rs::model::FeedType::Atom => "Atom",
-        feed_rs::model::FeedType::JSON => "JSON",
-        feed_rs::model::FeedType::RSS0 => "RSS",
-        feed_rs::model::FeedType::RSS1 => "RSS",
-        feed_rs::model::FeedType::RSS2 => "RSS",
-    })
-    .fetch_one(&mut *tx)
-    .await?;
+        sqlx::query("insert into feeds (title, feed_link) values ('slow', ?)")
+            .bind(format!("http://{slow_addr}/feed.xml"))
+            .execute(&pool)
+            .await
+            .unwrap();
 
-    for entry in &feed.entries {
-        sqlx::query(
-            "
-            insert into entries (feed_id, title, author, pub_date, content, link)
-            values (?1, ?2, ?3, ?4, ?5, ?6)
-            ",
+        set_password(&pool, "reader", "hunter2").await.unwrap();
+        let token = create_session(&pool, 1).await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            format!("{SESSION_COOKIE}={token}").parse().unwrap(),
+        );
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+
+        let refresh = tokio::spawn(client.put(format!("http://{addr}/feeds/1/refresh")).send());
+
+        // the refresh is now stuck waiting on the slow server
+        connected_rx.recv().await.unwrap();
+
+        let page_loads = ["/", "/feeds/1", "/entries", "/starred"]
+            .map(|page| client.get(format!("http://{addr}{page}")).send());
+
+        let responses = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            futures_util::future::try_join_all(page_loads),
         )
-        .bind(feed_id)
-        .bind(entry.title.as_ref().map(|title| &title.content))
-        .bind(entry.authors.first().map(|author| &author.name))
-        .bind(entry.published)
-        .bind(entry.content.as_ref().map(|content| &content.body))
-        .bind(entry.links.first().map(|link| &link.href))
-        .execute(&mut *tx)
-        .await?;
+        .await
+        .expect("page loads should not wait on the stalled refresh")
+        .unwrap();
+
+        for response in responses {
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        assert!(!refresh.is_finished());
+
+        refresh.abort();
+        remove_test_database(pool, &database).await;
     }
 
-    tx.commit().await?;
+    #[tokio::test]
+    async fn entry_pages_cover_every_entry_once() {
+        let (state, database) = test_state("cursor").await;
+        let pool = state.pool.clone();
 
-    Ok(())
-}
+        sqlx::query("insert into feeds (title, feed_link) values ('feed', 'http://example.com')")
+            .execute(&pool)
+            .await
+            .unwrap();
 
-async fn feed_create(
-    headers: HeaderMap,
-    state: State<Arc<Mutex<AppState>>>,
-) -> Result<impl IntoResponse, AppError> {
-    match do_feed_create(headers, state).await {
-        Ok(()) => {
-            let mut headers = HeaderMap::new();
-            headers.insert("HX-Location", "/".parse().unwrap());
-            Ok((headers, "").into_response())
-        }
-        Err(e) => {
-            let (status_code, error_message) = match e {
-                FeedCreateError::BadInput(s) => (StatusCode::BAD_REQUEST, s.to_string()),
-                FeedCreateError::NetworkError(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Unable to fetch remote feed: {e}"),
-                ),
-                FeedCreateError::FeedParseError(e) => (
-                    StatusCode::UNPROCESSABLE_ENTITY,
-                    format!("Could not parse feed: {e}"),
-                ),
-                FeedCreateError::DatabaseError(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Database error: {e}"),
-                ),
+        // enough for three pages, with runs of nulls and empty strings
+        // longer than a page, and ties on the sort values
+        for i in 0..130 {
+            let value = match i % 5 {
+                0 | 1 => None,
+                2 => Some(String::new()),
+                _ => Some(format!("2024-01-{:02}", i % 7 + 1)),
             };
 
-            let mut headers = HeaderMap::new();
+            sqlx::query(
+                "
+                insert into entries (feed_id, guid, title, pub_date, read_at)
+                values (1, ?1, ?2, ?2, ?2)",
+            )
+            .bind(i.to_string())
+            .bind(value)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
 
-            headers.insert(
-                "HX-Trigger",
-                axum::Json(format!("{{\"feedCreateError\":\"{error_message}\"}}"))
-                    .parse()
-                    .unwrap(),
-            );
+        for column in [
+            EntriesSortColumn::Title,
+            EntriesSortColumn::PubDate,
+            EntriesSortColumn::ReadAt,
+        ] {
+            for dir in [SortDirection::Asc, SortDirection::Desc] {
+                let sort = Sort {
+                    sort: Some(column),
+                    dir: Some(dir),
+                };
 
-            Ok((status_code, headers, "").into_response())
-        }
-    }
-}
+                let expected: Vec<i64> = sqlx::query_scalar(&format!(
+                    "select id from entries order by {0} {1}, id {1}",
+                    column.expression(),
+                    dir.as_sql()
+                ))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
 
-// TODO do similar error handling as adding feed
-//
-// get feed entries
-// TODO v2: if cache miss
-// get links for challenger entries
-// get links for existing entries
-// set = remote_entries - existing_entries
-// in transaction:
-// - for entry in set: insert
-// - update feed refreshed_at
-// - TODO v2: update_feed_etag
-async fn feed_refresh(
-    state: State<Arc<Mutex<AppState>>>,
-    Path(feed_id): Path<i64>,
-) -> Result<impl IntoResponse, AppError> {
-    let state = state.lock().await;
-    let mut conn = state.pool.acquire().await?;
+                let mut cursor = EntriesCursor::default();
+                let mut seen = vec![];
 
-    let (feed_link,): (String,) = sqlx::query_as(
-        "
-    select
-        feed_link
-    from feeds
-    where id = ?",
-    )
-    .bind(feed_id)
-    .fetch_one(&mut *conn)
-    .await?;
+                loop {
+                    let mut qb: sqlx::QueryBuilder<Sqlite> = sqlx::QueryBuilder::new(format!(
+                        "select entries.id, {} from entries where true ",
+                        column.expression()
+                    ));
 
-    let http_client = state.http_client.clone();
+                    cursor.push_page(&mut qb, sort);
 
-    let challenger_feed = http_client
-        .get(feed_link)
-        .send()
-        .await?
-        .error_for_status()?
-        .bytes()
-        .await?;
+                    let mut page: Vec<(i64, Option<String>)> =
+                        qb.build_query_as().fetch_all(&pool).await.unwrap();
 
-    let challenger_feed = feed_rs::parser::parse(&*challenger_feed)?;
+                    let more = page.len() as i64 > ENTRIES_PAGE_SIZE;
+                    page.truncate(ENTRIES_PAGE_SIZE as usize);
 
-    let existing_entries_links: HashSet<String> = sqlx::query_as(
-        "
-    select
-        link
-    from entries
-    where feed_id = ?",
-    )
-    .bind(feed_id)
-    .fetch_all(&mut *conn)
-    .await?
-    .into_iter()
-    .map(|(link,): (String,)| link)
-    .collect();
+                    seen.extend(page.iter().map(|(id, _)| *id));
 
-    let new_entries = challenger_feed
-        .entries
-        .iter()
-        .filter(|entry| !existing_entries_links.contains(&entry.links.first().unwrap().href));
+                    if !more {
+                        break;
+                    }
 
-    let mut tx = conn.begin().await?;
+                    let (id, value) = page.last().unwrap();
 
-    let mut new_entries_count = 0;
+                    // through the query string and extractor, the way "Load more" sends it back
+                    let uri: Uri = format!(
+                        "/feeds/1?{}&{}",
+                        sort.query_string(),
+                        EntriesCursor::after(value.as_deref(), *id).query_string()
+                    )
+                    .parse()
+                    .unwrap();
 
-    for entry in new_entries {
-        sqlx::query(
-            "
-            insert into entries (feed_id, title, author, pub_date, content, link)
-            values (?1, ?2, ?3, ?4, ?5, ?6)
-            ",
-        )
-        .bind(feed_id)
-        .bind(entry.title.as_ref().map(|title| &title.content))
-        .bind(entry.authors.first().map(|author| &author.name))
-        .bind(entry.published)
-        .bind(entry.content.as_ref().map(|content| &content.body))
-        .bind(entry.links.first().map(|link| &link.href))
-        .execute(&mut *tx)
-        .await?;
+                    cursor = Query::<FeedShowParams>::try_from_uri(&uri)
+                        .unwrap()
+                        .0
+                        .cursor;
+                }
+
+                assert_eq!(seen, expected, "sorting by {column:?} {dir:?}");
+            }
+        }
 
-        new_entries_count += 1;
+        remove_test_database(pool, &database).await;
     }
 
-    sqlx::query(
-        "
-        update feeds
-        set refreshed_at = ?
-        where id = ?",
-    )
-    .bind(chrono::Utc::now())
-    .bind(feed_id)
-    .execute(&mut *tx)
-    .await?;
+    #[tokio::test]
+    async fn requires_a_login() {
+        let (state, database) = test_state("login").await;
+        let pool = state.pool.clone();
 
-    tx.commit().await?;
+        set_password(&pool, "reader", "hunter2").await.unwrap();
 
-    Ok(html! {
-        div
-            id="refresh-result"
-            class="fade-me-out"
-            // after 5 seconds, fire the request
-            hx-trigger="load delay:5s"
-            // allow the swap to take 3 seconds.
-            // this is the same amount of time as the transition.
-            hx-swap="delete swap:3s"
-            // empty response endpoint
-            hx-delete="/empty"
-        {
-            (format!("added {new_entries_count} new entries"))
-        }
-    })
-}
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
 
-async fn empty() -> impl IntoResponse {
-    ""
-}
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
 
-async fn static_handler(uri: Uri) -> impl IntoResponse {
-    let mut path = uri.path().trim_start_matches('/').to_string();
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
 
-    if path.starts_with("dist/") {
-        path = path.replace("dist/", "");
-    }
+        let response = client.get(format!("http://{addr}/")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "/login");
 
-    StaticFile(path)
-}
+        let response = client
+            .post(format!("http://{addr}/feeds"))
+            .header("HX-Request", "true")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["HX-Redirect"], "/login");
 
-#[instrument(skip(conn))]
-async fn initialize_db(conn: &mut sqlx::SqliteConnection) -> anyhow::Result<()> {
-    let mut tx = conn.begin().await?;
+        let response = client
+            .get(format!("http://{addr}/login"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-    let (schema_version,): (u64,) = sqlx::query_as("PRAGMA user_version")
-        .fetch_optional(&mut *tx)
-        .await?
-        .unwrap_or((0,));
+        let response = client
+            .post(format!("http://{addr}/login"))
+            .form(&[("username", "reader"), ("password", "wrong")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!response.headers().contains_key("set-cookie"));
 
-    if schema_version == 0 {
-        tx.execute("PRAGMA user_version=1").await?;
+        let response = client
+            .post(format!("http://{addr}/login"))
+            .form(&[("username", "reader"), ("password", "hunter2")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
 
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS feeds (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        title TEXT,
-        feed_link TEXT,
-        link TEXT,
-        feed_kind TEXT,
-        refreshed_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-        )
-        .execute(&mut *tx)
-        .await?;
+        let cookie = response.headers()["set-cookie"].to_str().unwrap();
+        let cookie = cookie.split(';').next().unwrap().to_string();
 
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS entries (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        feed_id INTEGER,
-        title TEXT,
-        author TEXT,
-        pub_date TIMESTAMP,
-        description TEXT,
-        content TEXT,
-        link TEXT,
-        read_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-        )
-        .execute(&mut *tx)
-        .await?;
+        let token = cookie.strip_prefix(&format!("{SESSION_COOKIE}=")).unwrap();
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index
-        ON entries (feed_id, pub_date, inserted_at)",
-        )
-        .execute(&mut *tx)
-        .await?;
-    }
+        let (stored_in_plaintext,): (bool,) =
+            sqlx::query_as("select exists (select 1 from sessions where token_hash = ?)")
+                .bind(token)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(!stored_in_plaintext);
 
-    if schema_version <= 1 {
-        tx.execute("PRAGMA user_version=2").await?;
+        let response = client
+            .get(format!("http://{addr}/"))
+            .header("cookie", &cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        sqlx::query("ALTER TABLE feeds ADD COLUMN latest_etag TEXT")
-            .execute(&mut *tx)
-            .await?;
-    }
+        // resetting the password logs everyone out
+        set_password(&pool, "reader", "hunter3").await.unwrap();
 
-    if schema_version <= 2 {
-        tx.execute("PRAGMA user_version=3").await?;
+        let response = client
+            .get(format!("http://{addr}/"))
+            .header("cookie", &cookie)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
 
-        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS feeds_feed_link ON feeds (feed_link)")
-            .execute(&mut *tx)
-            .await?;
+        remove_test_database(pool, &database).await;
     }
 
-    tx.commit().await?;
+    /// writes a new self-signed certificate for localhost, returning it as PEM
+    fn write_self_signed(cert_path: &std::path::Path, key_path: &std::path::Path) -> String {
+        let certified_key =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
 
-    Ok(())
-}
+        let cert = certified_key.cert.pem();
 
-struct AppError(anyhow::Error);
+        std::fs::write(cert_path, &cert).unwrap();
+        std::fs::write(key_path, certified_key.key_pair.serialize_pem()).unwrap();
 
-// Tell axum how to convert `AppError` into a response.
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
-        )
-            .into_response()
+        cert
     }
-}
 
-impl<E> From<E> for AppError
-where
-    E: Into<anyhow::Error>,
-{
-    fn from(err: E) -> Self {
-        Self(err.into())
+    /// a client that only trusts `cert`
+    fn client_trusting(cert: &str, addr: std::net::SocketAddr) -> reqwest::Client {
+        reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(cert.as_bytes()).unwrap())
+            .tls_built_in_root_certs(false)
+            .resolve("localhost", addr)
+            .build()
+            .unwrap()
     }
-}
 
-#[derive(Embed)]
-#[folder = "dist/"]
-struct Asset;
+    #[tokio::test]
+    async fn serves_tls_and_reloads_the_certificate() {
+        let dir = std::env::temp_dir();
+        let cert_path = dir.join(format!("r2-test-{}-cert.pem", std::process::id()));
+        let key_path = dir.join(format!("r2-test-{}-key.pem", std::process::id()));
 
-pub struct StaticFile<T>(pub T);
+        let first_cert = write_self_signed(&cert_path, &key_path);
 
-impl<T> IntoResponse for StaticFile<T>
-where
-    T: Into<String>,
-{
-    fn into_response(self) -> Response {
-        let path = self.0.into();
+        let certificate =
+            Arc::new(ReloadingCertificate::load(cert_path.clone(), key_path.clone()).unwrap());
 
-        match Asset::get(path.as_str()) {
-            Some(content) => {
-                let mime = mime_guess::from_path(path).first_or_octet_stream();
-                (
-                    [(axum::http::header::CONTENT_TYPE, mime.as_ref())],
-                    content.data,
-                )
-                    .into_response()
-            }
-            None => (StatusCode::NOT_FOUND, "404 Not Found").into_response(),
-        }
-    }
-}
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
 
-#[derive(Debug)]
-struct AppState {
-    pool: sqlx::Pool<Sqlite>,
-    http_client: reqwest::Client,
-}
+        let router = Router::new().route("/", get(|| async { "ok" }));
 
-#[derive(Debug, Parser)]
-struct Config {
-    #[arg(long, env, default_value = "feeds.db")]
-    database: String,
-    #[arg(long, env, default_value = "3000")]
-    port: u16,
-}
+        tokio::spawn(serve(
+            Listener::Tcp(listener),
+            router,
+            Some(tls_acceptor(certificate.clone())),
+        ));
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+        tokio::spawn(
+            certificate
+                .clone()
+                .watch(std::time::Duration::from_millis(50)),
+        );
 
-    let config = Config::parse();
+        let url = format!("https://localhost:{}/", addr.port());
 
-    let opts =
-        sqlx::sqlite::SqliteConnectOptions::from_str(&format!("sqlite://{}", config.database))?
-            .busy_timeout(std::time::Duration::from_secs(5))
-            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
-            .create_if_missing(true)
-            .foreign_keys(true);
+        let response = client_trusting(&first_cert, addr)
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
 
-    let pool = sqlx::SqlitePool::connect_with(opts).await?;
+        assert_eq!(response.text().await.unwrap(), "ok");
 
-    let mut conn = pool.acquire().await?;
+        let second_cert = write_self_signed(&cert_path, &key_path);
 
-    initialize_db(&mut conn).await?;
+        // the watcher should notice the new files and start serving them
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                if let Ok(response) = client_trusting(&second_cert, addr).get(&url).send().await {
+                    return response;
+                }
 
-    let http_client = reqwest::Client::new();
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("the new certificate should be served once the files change");
 
-    let state = Arc::new(Mutex::new(AppState { pool, http_client }));
+        assert_eq!(response.text().await.unwrap(), "ok");
 
-    let router = Router::new()
-        .route("/", get(feed_index))
-        .route("/feeds", post(feed_create))
-        .route("/feeds/{feed_id}", get(feed_show))
-        .route("/feeds/{feed_id}/refresh", put(feed_refresh))
-        .route("/entries/{entry_id}", get(entry_show).put(entry_update))
-        .route("/dist/{*file}", get(static_handler))
-        .route("/empty", delete(empty))
-        .with_state(state)
-        .layer(tower_http::compression::CompressionLayer::new());
+        assert!(
+            client_trusting(&first_cert, addr)
+                .get(&url)
+                .send()
+                .await
+                .is_err()
+        );
 
-    #[cfg(debug_assertions)]
-    let router = router.layer(tower_livereload::LiveReloadLayer::new());
+        let _ = std::fs::remove_file(&cert_path);
+        let _ = std::fs::remove_file(&key_path);
+    }
+
+    #[tokio::test]
+    async fn redirects_http_to_https() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::HOST,
+            "example.com:8080".parse().unwrap(),
+        );
 
-    let listener = tokio::net::TcpListener::bind("localhost:4000").await?;
+        let response = redirect_to_https(
+            State(8443),
+            headers,
+            "/feeds/1?entries_visibility=all".parse().unwrap(),
+        )
+        .await;
 
-    axum::serve(listener, router).await?;
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers()[axum::http::header::LOCATION],
+            "https://example.com:8443/feeds/1?entries_visibility=all"
+        );
 
-    Ok(())
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::HOST, "[::1]:8080".parse().unwrap());
+
+        let response = redirect_to_https(State(443), headers, "/".parse().unwrap()).await;
+
+        assert_eq!(
+            response.headers()[axum::http::header::LOCATION],
+            "https://[::1]/"
+        );
+    }
 }